@@ -0,0 +1,132 @@
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::lines_writer::LinesWriter;
+use crate::offset::{HorizontalOffset, VerticalOffset};
+use crate::registers::Standard;
+use crate::{Attributes, COLUMN, MAX7456, ROW};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Nudge {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Interactive display position calibration, draws a border along
+/// visible screen edges while user nudges it to fit the monitor
+pub struct Calibration {
+    border: u8,
+    rows: usize,
+    horizontal: HorizontalOffset,
+    vertical: VerticalOffset,
+}
+
+impl Calibration {
+    pub fn new(standard: Standard, border: u8) -> Self {
+        let (horizontal, vertical) = Default::default();
+        Self { border, rows: standard.rows(), horizontal, vertical }
+    }
+
+    pub fn offsets(&self) -> (HorizontalOffset, VerticalOffset) {
+        (self.horizontal, self.vertical)
+    }
+
+    /// Reads current offsets and draws border, erase display memory once done
+    pub fn start<E, PE, SPI, CS, RST>(
+        &mut self,
+        max7456: &mut MAX7456<SPI, CS, RST>,
+    ) -> Result<(), E>
+    where
+        SPI: Write<u8, Error = E> + Transfer<u8, Error = E>,
+        CS: OutputPin<Error = PE>,
+    {
+        self.horizontal = max7456.horizontal_offset()?;
+        self.vertical = max7456.vertical_offset()?;
+        let mut lines = [[0u8; COLUMN]; ROW];
+        for (row, line) in lines[..self.rows].iter_mut().enumerate() {
+            if row == 0 || row == self.rows - 1 {
+                *line = [self.border; COLUMN];
+            } else {
+                line[0] = self.border;
+                line[COLUMN - 1] = self.border;
+            }
+        }
+        let mut writer = LinesWriter::new(&lines, Attributes::default());
+        let mut buffer = [0u8; 64];
+        loop {
+            let display = writer.write(&mut buffer);
+            if display.0.is_empty() {
+                return Ok(());
+            }
+            max7456.write_display(&display)?;
+        }
+    }
+
+    /// Moves display by one pixel, returns false if already at limit
+    pub fn nudge<E, PE, SPI, CS, RST>(
+        &mut self,
+        max7456: &mut MAX7456<SPI, CS, RST>,
+        nudge: Nudge,
+    ) -> Result<bool, E>
+    where
+        SPI: Write<u8, Error = E> + Transfer<u8, Error = E>,
+        CS: OutputPin<Error = PE>,
+    {
+        match nudge {
+            Nudge::Up | Nudge::Down => {
+                let pixels = if nudge == Nudge::Up { -1 } else { 1 };
+                match self.vertical.checked_add(pixels) {
+                    Some(offset) => self.vertical = offset,
+                    None => return Ok(false),
+                }
+                max7456.set_vertical_offset_to(self.vertical)?;
+            }
+            Nudge::Left | Nudge::Right => {
+                let pixels = if nudge == Nudge::Left { -1 } else { 1 };
+                match self.horizontal.checked_add(pixels) {
+                    Some(offset) => self.horizontal = offset,
+                    None => return Ok(false),
+                }
+                max7456.set_horizontal_offset_to(self.horizontal)?;
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Calibration, Nudge};
+    use crate::offset::{HorizontalOffset, VerticalOffset};
+    use crate::registers::{Registers, Standard};
+    use crate::simulator::Simulator;
+    use crate::testing::Recorder;
+    use crate::MAX7456;
+
+    #[test]
+    fn test_calibration() {
+        let recorder = Recorder::with_simulator(Simulator::new());
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        max7456.set_vertical_offset_to(VerticalOffset::new(14).unwrap()).unwrap();
+        let mut calibration = Calibration::new(Standard::NTSC, b'#');
+        calibration.start(&mut max7456).unwrap();
+        recorder.simulator(|s| {
+            assert_eq!(s.line(0), [b'#'; 30]);
+            assert_eq!(s.line(12), [b'#'; 30]);
+            assert_eq!((s.character(6, 0), s.character(6, 1)), (b'#', 0));
+            assert_eq!(s.character(6, 29), b'#');
+            assert_eq!(s.line(13), [0; 30]);
+        });
+
+        assert!(calibration.nudge(&mut max7456, Nudge::Down).unwrap());
+        assert!(!calibration.nudge(&mut max7456, Nudge::Down).unwrap());
+        assert!(calibration.nudge(&mut max7456, Nudge::Left).unwrap());
+        let expected = (HorizontalOffset::new(-1).unwrap(), VerticalOffset::new(15).unwrap());
+        assert_eq!(calibration.offsets(), expected);
+        let hos = recorder.simulator(|s| s.register(Registers::HorizentalOffset)).unwrap();
+        assert_eq!(hos, 0x1F);
+        assert_eq!(max7456.vertical_offset().unwrap(), VerticalOffset::new(15).unwrap());
+    }
+}
@@ -0,0 +1,242 @@
+//! Import SPI captures exported by logic analyzer software as CSV,
+//! and replay them through disassembler and simulator
+
+use std::fs;
+use std::path::Path;
+
+use crate::disassembler::{Disassembler, Operation};
+use crate::simulator::Simulator;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    Io(std::io::ErrorKind),
+    MissingColumn(&'static str),
+    InvalidValue { line: usize },
+}
+
+/// Bytes exchanged within one CS low period
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Transaction {
+    pub mosi: Vec<u8>,
+    /// `None` where not captured, write-only captures may leave MISO empty
+    pub miso: Vec<Option<u8>>,
+}
+
+/// Read slot where captured MISO differs from simulated chip
+#[derive(Debug, PartialEq)]
+pub struct Mismatch {
+    pub transaction: usize,
+    pub offset: usize,
+    pub simulated: u8,
+    pub captured: u8,
+}
+
+enum Framing {
+    /// CS level column, 0 or low for selected
+    ChipSelect(usize),
+    /// Packet ID column, each packet is one transaction
+    PacketId(usize),
+    /// Row type column with enable, result and disable rows
+    RowType(usize),
+}
+
+fn parse_byte(field: &str) -> Option<u8> {
+    let field = field.trim();
+    match field.strip_prefix("0x").or_else(|| field.strip_prefix("0X")) {
+        Some(hex) => u8::from_str_radix(hex, 16).ok(),
+        None => field.parse().ok(),
+    }
+}
+
+fn split(line: &str) -> Vec<&str> {
+    line.split(',').map(|field| field.trim().trim_matches('"').trim()).collect()
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Capture {
+    pub transactions: Vec<Transaction>,
+}
+
+impl Capture {
+    /// Accepts CSV with header naming MOSI and MISO columns, framed by
+    /// either a CS level column, a Packet ID column or enable/disable rows
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let mut lines = text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+        let header: Vec<String> = match lines.next() {
+            Some((_, line)) => split(line).iter().map(|h| h.to_lowercase()).collect(),
+            None => return Ok(Self::default()),
+        };
+        let column = |name: &str| header.iter().position(|h| h == name);
+        let mosi = column("mosi").ok_or(Error::MissingColumn("MOSI"))?;
+        let miso = column("miso").ok_or(Error::MissingColumn("MISO"))?;
+        let framing = if let Some(index) = column("cs").or_else(|| column("enable")) {
+            Framing::ChipSelect(index)
+        } else if let Some(index) = column("packet id") {
+            Framing::PacketId(index)
+        } else if let Some(index) = column("type") {
+            Framing::RowType(index)
+        } else {
+            return Err(Error::MissingColumn("CS"));
+        };
+
+        let mut capture = Self::default();
+        let mut current: Option<Transaction> = None;
+        let mut packet_id = None;
+        for (number, line) in lines {
+            let fields = split(line);
+            let invalid = || Error::InvalidValue { line: number + 1 };
+            let field = |index: usize| fields.get(index).copied().unwrap_or_default();
+            let selected = match framing {
+                Framing::ChipSelect(index) => match field(index).to_lowercase().as_str() {
+                    "0" | "low" => true,
+                    "1" | "high" => false,
+                    _ => return Err(invalid()),
+                },
+                Framing::PacketId(index) => {
+                    let id = Some(field(index).to_string());
+                    if packet_id != id {
+                        capture.transactions.extend(current.take());
+                        packet_id = id;
+                    }
+                    true
+                }
+                Framing::RowType(index) => match field(index) {
+                    "enable" => {
+                        current = Some(Transaction::default());
+                        continue;
+                    }
+                    "disable" => {
+                        capture.transactions.extend(current.take());
+                        continue;
+                    }
+                    _ => current.is_some(),
+                },
+            };
+            if !selected {
+                capture.transactions.extend(current.take());
+                continue;
+            }
+            let transaction = current.get_or_insert_with(Transaction::default);
+            transaction.mosi.push(parse_byte(field(mosi)).ok_or_else(invalid)?);
+            let miso = match field(miso) {
+                "" => None,
+                miso => Some(parse_byte(miso).ok_or_else(invalid)?),
+            };
+            transaction.miso.push(miso);
+        }
+        capture.transactions.extend(current.take());
+        Ok(capture)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let text = fs::read_to_string(path).map_err(|e| Error::Io(e.kind()))?;
+        Self::parse(&text)
+    }
+
+    /// Operations of every transaction, tagged with transaction index
+    pub fn operations(&self) -> impl Iterator<Item = (usize, Operation)> + '_ {
+        self.transactions
+            .iter()
+            .enumerate()
+            .flat_map(|(i, t)| Disassembler::new(&t.mosi).map(move |op| (i, op)))
+    }
+
+    /// Feed MOSI into simulator, returns read slots whose captured MISO
+    /// differs from what the simulated chip would answer, uncaptured ones skipped
+    pub fn replay(&self, simulator: &mut Simulator) -> Vec<Mismatch> {
+        let mut mismatches = Vec::new();
+        for (index, transaction) in self.transactions.iter().enumerate() {
+            simulator.select(true);
+            let bytes = transaction.mosi.iter().zip(transaction.miso.iter());
+            for (offset, (&mosi, &captured)) in bytes.enumerate() {
+                let reading = simulator.is_reading();
+                let simulated = simulator.exchange(mosi);
+                let captured = match captured {
+                    Some(captured) if reading => captured,
+                    _ => continue,
+                };
+                if simulated != captured {
+                    mismatches.push(Mismatch { transaction: index, offset, simulated, captured });
+                }
+            }
+            simulator.select(false);
+        }
+        mismatches
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Capture, Error, Mismatch, Transaction};
+    use crate::disassembler::Operation;
+    use crate::simulator::Simulator;
+
+    #[test]
+    fn test_chip_select_column() {
+        let csv = "Time [s],CS,MOSI,MISO
+                   0.1,0,0x06,0x00
+                   0.2,0,0x01,0x00
+                   0.3,1,0x00,0x00
+                   0.4,0,0x86,0x00
+                   0.5,0,0x00,0x01";
+        let capture = Capture::parse(csv).unwrap();
+        let expected = [
+            Transaction { mosi: vec![0x06, 0x01], miso: vec![Some(0), Some(0)] },
+            Transaction { mosi: vec![0x86, 0x00], miso: vec![Some(0), Some(1)] },
+        ];
+        assert_eq!(capture.transactions, expected);
+
+        let mut simulator = Simulator::new();
+        assert_eq!(capture.replay(&mut simulator), []);
+        assert_eq!(simulator.register(crate::registers::Registers::DisplayMemoryAddressLow), 1);
+    }
+
+    #[test]
+    fn test_packet_id_column() {
+        let csv = "Time [s],Packet ID,MOSI,MISO
+                   0.1,0,0x04,0x00
+                   0.2,0,0x00,0x00
+                   0.3,1,0xA0,0x00
+                   0.4,1,0x00,0x40";
+        let capture = Capture::parse(csv).unwrap();
+        assert_eq!(capture.transactions.len(), 2);
+        let operations: Vec<_> = capture.operations().map(|(i, op)| (i, op.to_string())).collect();
+        assert_eq!(operations[1], (1, Operation::ReadRegister(0xA0).to_string()));
+
+        let mismatch = Mismatch { transaction: 1, offset: 1, simulated: 0, captured: 0x40 };
+        assert_eq!(capture.replay(&mut Simulator::new()), [mismatch]);
+    }
+
+    #[test]
+    fn test_row_type_column() {
+        let csv = r#"name,type,start_time,duration,"mosi","miso"
+                     "SPI","enable",0.1,0,,
+                     "SPI","result",0.2,0,"0x07","0x00"
+                     "SPI","result",0.3,0,"0x41","0x00"
+                     "SPI","disable",0.4,0,,"#;
+        let capture = Capture::parse(csv).unwrap();
+        let expected = [Transaction { mosi: vec![0x07, 0x41], miso: vec![Some(0), Some(0)] }];
+        assert_eq!(capture.transactions, expected);
+        let mut simulator = Simulator::new();
+        capture.replay(&mut simulator);
+        assert_eq!(simulator.character(0, 0), 0x41);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(Capture::parse("CS,MOSI\n0,0x01"), Err(Error::MissingColumn("MISO")));
+        assert_eq!(Capture::parse("MOSI,MISO\n0x01,0x00"), Err(Error::MissingColumn("CS")));
+        let error = Error::InvalidValue { line: 3 };
+        assert_eq!(Capture::parse("CS,MOSI,MISO\n0,0x01,0\n0,0xZZ,0"), Err(error));
+        let error = Error::InvalidValue { line: 3 };
+        assert_eq!(Capture::parse("CS,MOSI,MISO\n0,0x01,0\n0,,0"), Err(error));
+        assert!(Capture::parse("CS,MOSI,MISO\n0,0x01,").is_ok());
+    }
+
+    #[test]
+    fn test_write_only() {
+        let capture = Capture::parse("CS,MOSI,MISO\n0,0x81,\n0,0x00,").unwrap();
+        assert_eq!(capture.transactions[0].miso, [None, None]);
+        assert_eq!(capture.replay(&mut Simulator::new()), []);
+    }
+}
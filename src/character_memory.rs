@@ -1,6 +1,8 @@
 use super::registers::{CharacterMemoryMode, Registers};
 
 pub const CHAR_DATA_SIZE: usize = 64;
+pub const CHAR_WIDTH: usize = 12; // unit pixel
+pub const CHAR_HEIGHT: usize = 18; // unit pixel
 pub const STORE_CHAR_BUFFER_SIZE: usize = 2 + CHAR_DATA_SIZE * 4 + 2;
 
 pub type CharData = [u8; CHAR_DATA_SIZE];
@@ -21,7 +23,7 @@ pub fn build_store_char_operation(data: &CharData, index: u8, output: &mut [u8])
     }
     output[2 + CHAR_DATA_SIZE * 4] = Registers::CharacterMemoryMode as u8;
     output[2 + CHAR_DATA_SIZE * 4 + 1] = CharacterMemoryMode::WriteToNVM as u8;
-    return true;
+    true
 }
 
 mod test {
@@ -32,7 +34,7 @@ mod test {
 
         let data: CharData = [0x55u8; CHAR_DATA_SIZE];
         let mut output = [0u8; STORE_CHAR_BUFFER_SIZE];
-        assert_eq!(super::build_store_char_operation(&data, 10, &mut output), true);
+        assert!(super::build_store_char_operation(&data, 10, &mut output));
         assert_eq!(output[0], Registers::CharacterMemoryAddressHigh as u8);
         assert_eq!(output[2], Registers::CharacterMemoryAddressLow as u8);
         assert_eq!(output[2 + CHAR_DATA_SIZE * 4 - 4], Registers::CharacterMemoryAddressLow as u8);
@@ -0,0 +1,169 @@
+use core::convert::TryFrom;
+
+use peripheral_register::Register;
+
+use crate::offset::{HorizontalOffset, VerticalOffset};
+use crate::registers::*;
+
+pub const ROW_BRIGHTNESS_REGISTERS: usize = 16;
+
+/// Whole chip configuration, defaults to power-on values
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Config {
+    pub standard: Standard,
+    pub sync_mode: SyncMode,
+    pub vertical_sync: VerticalSync,
+    pub display_enabled: bool,
+    pub video_buffer_enabled: bool,
+    /// Gray background instead of `background_brightness`
+    pub gray_background: bool,
+    /// `brightness!` value
+    pub background_brightness: u8,
+    /// 2, 4, 6 or 8 fields for 0, 1, 2, 3
+    pub blink_time: u8,
+    /// BT:BT, BT:2BT, BT:3BT, 3BT:BT for 0, 1, 2, 3
+    pub blink_duty_cycle: u8,
+    pub horizontal_offset: HorizontalOffset,
+    pub vertical_offset: VerticalOffset,
+    /// Raw row brightness registers
+    pub row_brightness: [u8; ROW_BRIGHTNESS_REGISTERS],
+    /// `rise_and_fall_time!` value
+    pub rise_and_fall_time: u8,
+    /// `insertion_mux_switch_time!` value
+    pub insertion_mux_switch_time: u8,
+    pub auto_black_level: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            standard: Standard::NTSC,
+            sync_mode: SyncMode::AutoSyncDetect,
+            vertical_sync: VerticalSync::Immediately,
+            display_enabled: false,
+            video_buffer_enabled: true,
+            gray_background: false,
+            background_brightness: crate::brightness!(28%),
+            blink_time: 1,
+            blink_duty_cycle: 3,
+            horizontal_offset: HorizontalOffset::default(),
+            vertical_offset: VerticalOffset::default(),
+            row_brightness: [0x01; ROW_BRIGHTNESS_REGISTERS],
+            rise_and_fall_time: crate::rise_and_fall_time!(60ns),
+            insertion_mux_switch_time: crate::insertion_mux_switch_time!(75ns),
+            auto_black_level: true,
+        }
+    }
+}
+
+impl Config {
+    pub(crate) fn video_mode_0(&self) -> u8 {
+        let mut vm0 = Register::<u8, VideoMode0>::new(0);
+        vm0.set(VideoMode0::Standard, self.standard as u8);
+        vm0.set(VideoMode0::SyncMode, self.sync_mode as u8);
+        vm0.set(VideoMode0::EnableDisplay, self.display_enabled as u8);
+        vm0.set(VideoMode0::VerticalSync, self.vertical_sync as u8);
+        vm0.set(VideoMode0::VideoBufferEnable, !self.video_buffer_enabled as u8);
+        vm0.value
+    }
+
+    pub(crate) fn video_mode_1(&self) -> u8 {
+        let mut vm1 = Register::<u8, VideoMode1>::new(0);
+        vm1.set(VideoMode1::BackgroundMode, self.gray_background as u8);
+        vm1.set(VideoMode1::Brightness, self.background_brightness);
+        vm1.set(VideoMode1::BlinkTime, self.blink_time);
+        vm1.set(VideoMode1::BlinkDutyCycle, self.blink_duty_cycle);
+        vm1.value
+    }
+
+    pub(crate) fn insertion_mux(&self) -> u8 {
+        let mut osdm = Register::<u8, OSDInsertionMuxRegister>::new(0);
+        osdm.set(OSDInsertionMuxRegister::RiseAndFallTime, self.rise_and_fall_time);
+        osdm.set(
+            OSDInsertionMuxRegister::InsertionMuxSwitchingTIme,
+            self.insertion_mux_switch_time,
+        );
+        osdm.value
+    }
+
+    /// Keeps factory preset bits of `black_level`
+    pub(crate) fn black_level(&self, black_level: u8) -> u8 {
+        let mut osdbl = Register::<u8, OSDBlackLevel>::new(black_level);
+        osdbl.set(OSDBlackLevel::AutoBlackLevelDisable, !self.auto_black_level as u8);
+        osdbl.value
+    }
+
+    pub(crate) fn set_video_mode_0(&mut self, value: u8) {
+        self.standard = Standard::try_from(field_value(value, VideoMode0::Standard)).unwrap();
+        self.sync_mode = SyncMode::try_from(field_value(value, VideoMode0::SyncMode)).unwrap();
+        self.display_enabled = field_value(value, VideoMode0::EnableDisplay) > 0;
+        let vertical_sync = field_value(value, VideoMode0::VerticalSync);
+        self.vertical_sync = VerticalSync::try_from(vertical_sync).unwrap();
+        self.video_buffer_enabled = field_value(value, VideoMode0::VideoBufferEnable) == 0;
+    }
+
+    pub(crate) fn set_video_mode_1(&mut self, value: u8) {
+        self.gray_background = field_value(value, VideoMode1::BackgroundMode) > 0;
+        self.background_brightness = field_value(value, VideoMode1::Brightness);
+        self.blink_time = field_value(value, VideoMode1::BlinkTime);
+        self.blink_duty_cycle = field_value(value, VideoMode1::BlinkDutyCycle);
+    }
+
+    pub(crate) fn set_insertion_mux(&mut self, value: u8) {
+        self.rise_and_fall_time = field_value(value, OSDInsertionMuxRegister::RiseAndFallTime);
+        self.insertion_mux_switch_time =
+            field_value(value, OSDInsertionMuxRegister::InsertionMuxSwitchingTIme);
+    }
+
+    pub(crate) fn set_black_level(&mut self, value: u8) {
+        self.auto_black_level = field_value(value, OSDBlackLevel::AutoBlackLevelDisable) == 0;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Config;
+    use crate::offset::{HorizontalOffset, VerticalOffset};
+    use crate::registers::{Registers, Standard, SyncMode};
+    use crate::simulator::Simulator;
+    use crate::testing::Recorder;
+    use crate::MAX7456;
+
+    #[test]
+    fn test_apply() {
+        let recorder = Recorder::with_simulator(Simulator::new());
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        let mut config = Config {
+            standard: Standard::PAL,
+            sync_mode: SyncMode::Internal,
+            display_enabled: true,
+            horizontal_offset: HorizontalOffset::new(-3).unwrap(),
+            vertical_offset: VerticalOffset::new(15).unwrap(),
+            auto_black_level: false,
+            ..Default::default()
+        };
+        config.row_brightness[15] = 0x0A;
+        max7456.apply(&config).unwrap();
+        let transactions = recorder.take_transactions();
+        assert_eq!(transactions.len(), 1 + 3 + 16 + 1 + 2 + 1);
+        assert_eq!(transactions[0], [0x00, 0x70]);
+        assert_eq!(transactions[2], [0x02, 0x1D]);
+        assert_eq!(transactions[3], [0x03, 0x1F]);
+        assert_eq!(transactions[19], [0x1F, 0x0A]);
+        assert_eq!(transactions[21], [0xEC, 0x00]);
+        assert_eq!(transactions[22], [0x6C, 0x10]);
+        assert_eq!(transactions[23], [0x00, 0x78]);
+        assert!(max7456.is_display_enabled());
+        let osdbl = recorder.simulator(|s| s.register(Registers::OSDBlackLevel)).unwrap();
+        assert_eq!(osdbl, 0x10);
+
+        assert_eq!(max7456.read_config().unwrap(), config);
+    }
+
+    #[test]
+    fn test_default() {
+        let recorder = Recorder::with_simulator(Simulator::new());
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        assert_eq!(max7456.read_config().unwrap(), Config::default());
+    }
+}
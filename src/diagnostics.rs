@@ -0,0 +1,175 @@
+use embedded_hal::blocking::delay::DelayUs;
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+use peripheral_register::Register;
+
+use crate::registers::{field_value, DisplayMemoryMode, OperationMode, Registers, Status};
+use crate::registers::{DMAH, DMAL, DMDI, DMDO, DMM};
+use crate::{COLUMN, MAX7456, ROW};
+
+// register and patterns written then read back, within register width
+const PATTERNS: [(Registers, [u8; 2]); 3] = [
+    (Registers::HorizentalOffset, [0x15, 0x2A]),
+    (Registers::VerticalOffset, [0x0A, 0x15]),
+    (Registers::VideoMode1, [0x5A, 0xA5]),
+];
+
+// display memory clear takes about 20us, give up after 100 polls
+const CLEAR_POLLS: u8 = 100;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ProbeReport {
+    pub status: u8,
+    /// Reserved status bit clear and not both NTSC and PAL detected,
+    /// missing chip usually reads 0x00 or 0xFF
+    pub status_sane: bool,
+    /// Patterns not read back as written
+    pub register_errors: u8,
+}
+
+impl ProbeReport {
+    pub fn is_present(&self) -> bool {
+        self.status_sane && self.register_errors == 0
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SelfTestReport {
+    pub probe: ProbeReport,
+    /// Display memory cells written then read back, twice each
+    pub cells_tested: u16,
+    pub cell_errors: u16,
+    /// Display memory address of first failed cell
+    pub first_error: Option<u16>,
+    /// Display memory clear afterwards never finished
+    pub clear_timeout: bool,
+}
+
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        self.probe.is_present() && self.cell_errors == 0 && !self.clear_timeout
+    }
+}
+
+fn pattern(address: u16, pass: u8) -> u8 {
+    let value = address as u8 ^ (address >> 8) as u8;
+    match pass {
+        0 => value,
+        _ => !value,
+    }
+}
+
+impl<E, PE, SPI, CS, RST> MAX7456<SPI, CS, RST>
+where
+    SPI: Write<u8, Error = E> + Transfer<u8, Error = E>,
+    CS: OutputPin<Error = PE>,
+{
+    /// Tells whether a chip is on the bus, registers probed are restored,
+    /// bypasses configuration shadow and write verification
+    pub fn probe(&mut self) -> Result<ProbeReport, E> {
+        let status: u8 = self.load(Registers::Status)?;
+        let both = field_value(status, Status::NTSCSignal) & field_value(status, Status::PALSignal);
+        let status_sane = status & 0x80 == 0 && both == 0;
+        let mut register_errors = 0;
+        for &(reg, patterns) in PATTERNS.iter() {
+            let original = self.read_register(reg as u8)?;
+            for &pattern in patterns.iter() {
+                self.send(reg as u8, pattern)?;
+                if self.read_register(reg as u8)? != pattern {
+                    register_errors += 1;
+                }
+            }
+            self.send(reg as u8, original)?;
+        }
+        Ok(ProbeReport { status, status_sane, register_errors })
+    }
+
+    /// Probes chip then writes and reads back every display memory cell,
+    /// display memory is cleared afterwards. Stops after probe if chip absent,
+    /// bypasses write verification as `probe` does
+    pub fn self_test(&mut self, delay: &mut dyn DelayUs<u8>) -> Result<SelfTestReport, E> {
+        let mut report = SelfTestReport { probe: self.probe()?, ..Default::default() };
+        if !report.probe.is_present() {
+            return Ok(report);
+        }
+        let size = (ROW * COLUMN) as u16;
+        let mode_8bit = Register::<u8, DisplayMemoryMode>::of(
+            DisplayMemoryMode::OperationMode,
+            OperationMode::Mode8Bit as u8,
+        );
+        for pass in 0..2 {
+            self.send(DMM, 0)?;
+            for address in 0..size {
+                self.send(DMAH, (address >> 8) as u8)?;
+                self.send(DMAL, address as u8)?;
+                self.send(DMDI, pattern(address, pass))?;
+            }
+            self.send(DMM, mode_8bit.value)?;
+            for address in 0..size {
+                self.send(DMAH, (address >> 8) as u8)?;
+                self.send(DMAL, address as u8)?;
+                let value = self.read_register(DMDO)?;
+                report.cells_tested += 1;
+                if value != pattern(address, pass) {
+                    report.cell_errors += 1;
+                    report.first_error = report.first_error.or(Some(address));
+                }
+            }
+        }
+        self.start_clear_display()?;
+        report.clear_timeout = true;
+        for _ in 0..CLEAR_POLLS {
+            delay.delay_us(20);
+            if self.is_display_cleared()? {
+                report.clear_timeout = false;
+                break;
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ProbeReport;
+    use crate::registers::Registers;
+    use crate::simulator::Simulator;
+    use crate::testing::{MockDelay, Recorder};
+    use crate::MAX7456;
+
+    #[test]
+    fn test_present() {
+        let recorder = Recorder::with_simulator(Simulator::new());
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        recorder.simulator(|s| s.transaction(&[0x07, 0x41]));
+        let report = max7456.self_test(&mut MockDelay::default()).unwrap();
+        assert!(report.passed());
+        assert_eq!(report.cells_tested, 960);
+        assert_eq!(report.first_error, None);
+        let restored =
+            recorder.simulator(|s| (s.register(Registers::VideoMode1), s.character(0, 0)));
+        assert_eq!(restored.unwrap(), (0x47, 0));
+    }
+
+    #[test]
+    fn test_absent() {
+        let recorder = Recorder::new();
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        let expected = ProbeReport { status: 0, status_sane: true, register_errors: 6 };
+        assert_eq!(max7456.probe().unwrap(), expected);
+        assert!(!expected.is_present());
+        recorder.respond(&[0xFF]);
+        assert!(!max7456.probe().unwrap().status_sane);
+
+        max7456.set_write_verify(Some(3));
+        max7456.probe().unwrap();
+        assert_eq!(max7456.verify_counters(), Default::default());
+        assert_eq!(max7456.shadow.probe(), None);
+
+        recorder.take_transactions();
+        let report = max7456.self_test(&mut MockDelay::default()).unwrap();
+        assert_eq!((report.cells_tested, report.clear_timeout), (0, false));
+        assert!(!report.passed());
+        assert_eq!(recorder.transactions().len(), 1 + 3 * 6);
+    }
+}
@@ -0,0 +1,283 @@
+use core::fmt;
+
+use crate::registers::{
+    field_value, CharacterAttribute, DisplayMemoryAddressHigh, DisplayMemoryMode, OperationMode,
+    DMAH, DMAL, DMDI, DMM,
+};
+use crate::{Attributes, Display};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Operation {
+    /// Display memory mode write, attributes apply to following 16bit mode writes
+    SetAttributes {
+        attributes: Attributes,
+        mode: OperationMode,
+        auto_increment: bool,
+        clear: bool,
+    },
+    SetAddress(u16),
+    /// Select character address byte or attribute byte for 8bit mode
+    SelectAttributeByte(bool),
+    WriteChar(u8),
+    /// Attribute byte written in 8bit mode
+    WriteAttributes(Attributes),
+    /// 0xFF written in auto increment mode
+    EndAutoIncrement,
+    WriteRegister(u8, u8),
+    ReadRegister(u8),
+    /// Trailing register address without value
+    Truncated(u8),
+}
+
+fn attributes_of(value: u8) -> Attributes {
+    Attributes {
+        local_background_control: field_value(value, CharacterAttribute::LocalBackgroundControl)
+            > 0,
+        blink: field_value(value, CharacterAttribute::Blink) > 0,
+        revert: field_value(value, CharacterAttribute::Invert) > 0,
+    }
+}
+
+impl fmt::Display for Attributes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let flags = [
+            (self.local_background_control, "local-background"),
+            (self.blink, "blink"),
+            (self.revert, "invert"),
+        ];
+        let mut first = true;
+        for (_, name) in flags.iter().filter(|(set, _)| *set) {
+            write!(f, "{}{}", if first { "" } else { "|" }, name)?;
+            first = false;
+        }
+        if first {
+            write!(f, "none")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::SetAttributes { attributes, mode, auto_increment, clear } => {
+                let mode = match mode {
+                    OperationMode::Mode16Bit => "16bit",
+                    OperationMode::Mode8Bit => "8bit",
+                };
+                write!(f, "SetAttributes({}, {}", attributes, mode)?;
+                if *auto_increment {
+                    write!(f, ", auto-increment")?;
+                }
+                if *clear {
+                    write!(f, ", clear")?;
+                }
+                write!(f, ")")
+            }
+            Self::SetAddress(address) => write!(f, "SetAddress({})", address),
+            Self::SelectAttributeByte(select) => write!(f, "SelectAttributeByte({})", select),
+            Self::WriteChar(byte) => write!(f, "WriteChar(0x{:02X})", byte),
+            Self::WriteAttributes(attributes) => write!(f, "WriteAttributes({})", attributes),
+            Self::EndAutoIncrement => write!(f, "EndAutoIncrement"),
+            Self::WriteRegister(reg, value) => write!(f, "Write(0x{:02X}, 0x{:02X})", reg, value),
+            Self::ReadRegister(reg) => write!(f, "Read(0x{:02X})", reg),
+            Self::Truncated(reg) => write!(f, "Truncated(0x{:02X})", reg),
+        }
+    }
+}
+
+/// Decode SPI byte stream of register/value pairs into operations,
+/// consecutive display memory address high and low writes are merged
+pub struct Disassembler<'a> {
+    bytes: &'a [u8],
+    index: usize,
+    address: u16,
+    eight_bit: bool,
+    auto_increment: bool,
+    attribute_byte: bool,
+    pending: Option<Operation>,
+}
+
+impl<'a> Disassembler<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            index: 0,
+            address: 0,
+            eight_bit: false,
+            auto_increment: false,
+            attribute_byte: false,
+            pending: None,
+        }
+    }
+
+    /// Display memory address the next write goes to
+    pub fn address(&self) -> u16 {
+        self.address
+    }
+
+    fn next_pair(&mut self) -> Option<(u8, Option<u8>)> {
+        let reg = *self.bytes.get(self.index)?;
+        let value = self.bytes.get(self.index + 1).copied();
+        self.index += 2;
+        Some((reg, value))
+    }
+
+    fn peek_register(&self) -> Option<u8> {
+        self.bytes.get(self.index).copied()
+    }
+}
+
+impl<'a> Display<'a> {
+    pub fn operations(&self) -> Disassembler<'a> {
+        Disassembler::new(self.0)
+    }
+}
+
+impl<'a> Iterator for Disassembler<'a> {
+    type Item = Operation;
+
+    fn next(&mut self) -> Option<Operation> {
+        if let Some(operation) = self.pending.take() {
+            return Some(operation);
+        }
+        let (reg, value) = self.next_pair()?;
+        if reg & 0x80 > 0 {
+            return Some(Operation::ReadRegister(reg));
+        }
+        let value = match value {
+            Some(value) => value,
+            None => return Some(Operation::Truncated(reg)),
+        };
+        let operation = match reg {
+            DMM => {
+                self.eight_bit = field_value(value, DisplayMemoryMode::OperationMode) > 0;
+                self.auto_increment = field_value(value, DisplayMemoryMode::AutoIncrement) > 0;
+                let clear = field_value(value, DisplayMemoryMode::Clear) > 0;
+                if clear {
+                    self.auto_increment = false;
+                }
+                Operation::SetAttributes {
+                    attributes: attributes_of(value >> 3),
+                    mode: if self.eight_bit {
+                        OperationMode::Mode8Bit
+                    } else {
+                        OperationMode::Mode16Bit
+                    },
+                    auto_increment: self.auto_increment,
+                    clear,
+                }
+            }
+            DMAH => {
+                let attribute_byte =
+                    field_value(value, DisplayMemoryAddressHigh::ByteSelection) > 0;
+                let high = field_value(value, DisplayMemoryAddressHigh::Address8) as u16;
+                self.address = high << 8 | (self.address & 0xFF);
+                if self.peek_register() == Some(DMAL) {
+                    match self.next_pair()? {
+                        (_, Some(low)) => self.address = (self.address & 0x100) | low as u16,
+                        (_, None) => return Some(Operation::Truncated(DMAL)),
+                    }
+                }
+                if self.eight_bit && attribute_byte != self.attribute_byte {
+                    self.attribute_byte = attribute_byte;
+                    self.pending = Some(Operation::SetAddress(self.address));
+                    return Some(Operation::SelectAttributeByte(attribute_byte));
+                }
+                self.attribute_byte = attribute_byte;
+                Operation::SetAddress(self.address)
+            }
+            DMAL => {
+                self.address = (self.address & 0x100) | value as u16;
+                Operation::SetAddress(self.address)
+            }
+            DMDI if self.auto_increment && value == 0xFF => {
+                self.auto_increment = false;
+                Operation::EndAutoIncrement
+            }
+            DMDI => {
+                if self.auto_increment {
+                    self.address += 1;
+                }
+                match self.eight_bit && self.attribute_byte {
+                    true => Operation::WriteAttributes(attributes_of(value)),
+                    false => Operation::WriteChar(value),
+                }
+            }
+            _ => Operation::WriteRegister(reg, value),
+        };
+        Some(operation)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Operation::*;
+    use crate::incremental_writer::IncrementalWriter;
+    use crate::lines_writer::LinesWriter;
+    use crate::registers::OperationMode;
+    use crate::Attributes;
+
+    #[test]
+    fn test_lines_writer() {
+        let mut output = [0u8; 32];
+        let mut lines = [[0u8; 30]; 16];
+        lines[7][29] = b't';
+        lines[8][29] = b't';
+        let mut writer = LinesWriter::new(&lines, Default::default());
+        let display = writer.write(&mut output);
+        let operations: Vec<_> = display.operations().collect();
+        let attributes = Attributes::default();
+        let mode = OperationMode::Mode16Bit;
+        let expected = [
+            SetAttributes { attributes, mode, auto_increment: false, clear: false },
+            SetAddress(239),
+            WriteChar(0x74),
+            SetAddress(269),
+            WriteChar(0x74),
+        ];
+        assert_eq!(operations, expected);
+    }
+
+    #[test]
+    fn test_incremental_writer() {
+        let mut output = [0u8; 32];
+        let attributes = Attributes { blink: true, ..Default::default() };
+        let mut writer = IncrementalWriter::new(b"ok", 0, 0, attributes);
+        let display = writer.write(&mut output).unwrap();
+        let operations: Vec<String> = display.operations().map(|op| op.to_string()).collect();
+        let expected = [
+            "SetAttributes(blink, 16bit, auto-increment)",
+            "SetAddress(0)",
+            "WriteChar(0x6F)",
+            "WriteChar(0x6B)",
+            "EndAutoIncrement",
+        ];
+        assert_eq!(operations, expected);
+    }
+
+    #[test]
+    fn test_8bit_mode() {
+        let bytes = hex!("04 40 05 02 06 10 07 03 05 00 07 41 A0 00 02");
+        let operations: Vec<_> = super::Disassembler::new(&bytes).collect();
+        let attributes = Attributes { blink: true, revert: true, ..Default::default() };
+        let mode = OperationMode::Mode8Bit;
+        let expected = [
+            SetAttributes {
+                attributes: Default::default(),
+                mode,
+                auto_increment: false,
+                clear: false,
+            },
+            SelectAttributeByte(true),
+            SetAddress(16),
+            WriteAttributes(attributes),
+            SelectAttributeByte(false),
+            SetAddress(16),
+            WriteChar(0x41),
+            ReadRegister(0xA0),
+            Truncated(0x02),
+        ];
+        assert_eq!(operations, expected);
+    }
+}
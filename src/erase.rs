@@ -0,0 +1,103 @@
+use crate::registers::{
+    field_value, DisplayMemoryAddressHigh, DisplayMemoryMode, DMAH, DMAL, DMDI, DMM,
+};
+use crate::Display;
+
+// LBC, BLK and INV bits of display memory mode
+const ATTRIBUTES_MASK: u8 = 0b111 << 3;
+
+/// Turn writer output into a transaction erasing every cell it writes,
+/// in place, by replacing chars with `blank` and dropping attributes.
+///
+/// Addressed writes, auto increment runs with their 0xFF terminator and
+/// 8bit mode attribute bytes are supported, `null` register marks the end,
+/// any other register results in empty `Display`.
+pub fn erase(buffer: &mut [u8], blank: u8) -> Display<'_> {
+    let mut auto_increment = false;
+    let mut eight_bit = false;
+    let mut attribute_byte = false;
+    let mut length = 0;
+    for pair in buffer.chunks_exact_mut(2) {
+        match pair[0] {
+            DMM => {
+                eight_bit = field_value(pair[1], DisplayMemoryMode::OperationMode) > 0;
+                auto_increment = field_value(pair[1], DisplayMemoryMode::AutoIncrement) > 0;
+                pair[1] &= !ATTRIBUTES_MASK;
+            }
+            DMAH => {
+                attribute_byte = field_value(pair[1], DisplayMemoryAddressHigh::ByteSelection) > 0
+            }
+            DMAL => (),
+            DMDI if auto_increment && pair[1] == 0xFF => auto_increment = false,
+            DMDI if eight_bit && attribute_byte => pair[1] = 0,
+            DMDI => pair[1] = blank,
+            0 => break,
+            _ => return Display(&buffer[..0]),
+        }
+        length += 2;
+    }
+    Display(&buffer[..length])
+}
+
+#[cfg(test)]
+mod test {
+    use super::erase;
+    use crate::incremental_writer::IncrementalWriter;
+    use crate::lines_writer::LinesWriter;
+    use crate::simulator::Simulator;
+    use crate::Attributes;
+
+    #[test]
+    fn test_lines_writer() {
+        let mut output = [0u8; 32];
+        let mut lines = [[0u8; 30]; 16];
+        lines[7][29] = b't';
+        lines[8][29] = b't';
+        let attributes = Attributes { blink: true, ..Default::default() };
+        let mut writer = LinesWriter::new(&lines, attributes);
+        writer.write(&mut output);
+        let expected = hex!("04 00 05 00 06 EF 07 20 05 01 06 0D 07 20");
+        assert_eq!(erase(&mut output, b' ').0, expected);
+    }
+
+    #[test]
+    fn test_incremental_writer() {
+        let mut output = [0u8; 32];
+        let attributes = Attributes { revert: true, ..Default::default() };
+        let mut writer = IncrementalWriter::new(b"a\xFFb", 0, 0, attributes);
+        let length = writer.write(&mut output).unwrap().0.len();
+        let expected = hex!(
+            "04 01 05 00 06 00 07 00 07 FF 05 00 06 01 07 00
+             04 01 05 00 06 02 07 00 07 FF"
+        );
+        assert_eq!(erase(&mut output[..length], 0).0, expected);
+    }
+
+    #[test]
+    fn test_8bit_mode() {
+        let mut bytes = hex!("04 40 05 02 06 10 07 03 05 00 07 41");
+        let expected = hex!("04 40 05 02 06 10 07 00 05 00 07 20");
+        assert_eq!(erase(&mut bytes, b' ').0, expected);
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let mut bytes = hex!("09 0A 0A 00 0B 55");
+        assert_eq!(erase(&mut bytes, 0).0.len(), 0);
+    }
+
+    #[test]
+    fn test_erase_on_screen() {
+        let mut simulator = Simulator::new();
+        let mut output = [0u8; 32];
+        let attributes = Attributes { local_background_control: true, ..Default::default() };
+        let mut writer = IncrementalWriter::new(b"\xFFab", 2, 3, attributes);
+        let length = writer.write(&mut output).unwrap().0.len();
+        simulator.transaction(&output[..length]);
+        assert_eq!(simulator.line(2)[3..6], *b"\xFFab");
+
+        simulator.transaction(erase(&mut output[..length], 0).0);
+        assert_eq!(simulator.display_memory().iter().filter(|&&b| b > 0).count(), 0);
+        assert_eq!(simulator.attributes(2, 4), Attributes::default());
+    }
+}
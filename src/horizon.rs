@@ -0,0 +1,121 @@
+use core::cmp::min;
+
+use libm::tanf;
+
+use crate::character_memory::{CharData, CHAR_HEIGHT, CHAR_WIDTH};
+use crate::registers::Pixel;
+
+/// Number of glyphs in a horizon glyph family,
+/// each one draws the line 2 pixels lower than the previous one
+pub const HORIZON_STEPS: u8 = 9;
+
+const STEP_HEIGHT: usize = CHAR_HEIGHT / HORIZON_STEPS as usize;
+const BYTES_PER_PIXEL_ROW: usize = CHAR_WIDTH / 4;
+
+/// Build glyph of specified step, which is expected to be stored
+/// at `first_glyph + step` with `store_char`
+pub fn build_horizon_glyph(step: u8, output: &mut CharData) {
+    let fill = |pixel: u8| pixel * 0b01010101;
+    for byte in output.iter_mut() {
+        *byte = fill(Pixel::Transparent as u8);
+    }
+    let row = min(step, HORIZON_STEPS - 1) as usize * STEP_HEIGHT;
+    for (i, pixel) in [Pixel::White as u8, Pixel::Black as u8].iter().enumerate() {
+        let offset = (row + i) * BYTES_PER_PIXEL_ROW;
+        for byte in output[offset..offset + BYTES_PER_PIXEL_ROW].iter_mut() {
+            *byte = fill(*pixel);
+        }
+    }
+}
+
+/// Artificial horizon renderer, which writes horizon glyphs into
+/// a display buffer consumed by `LinesWriter`
+pub struct HorizonRenderer {
+    first_glyph: u8,
+    width: usize,
+    pixels_per_degree: f32,
+}
+
+impl HorizonRenderer {
+    /// `width` is number of columns the horizon spans, centered on screen
+    pub fn new(first_glyph: u8, width: usize, pixels_per_degree: f32) -> Self {
+        assert!(first_glyph as usize + HORIZON_STEPS as usize <= 0x100);
+        Self { first_glyph, width, pixels_per_degree }
+    }
+
+    fn is_horizon_glyph(&self, byte: u8) -> bool {
+        byte >= self.first_glyph && byte - self.first_glyph < HORIZON_STEPS
+    }
+
+    /// Roll and pitch in degree, positive roll for right wing down
+    /// and positive pitch for nose up
+    pub fn render<T: AsMut<[u8]>>(&self, roll: f32, pitch: f32, lines: &mut [T]) {
+        for line in lines.iter_mut() {
+            for byte in line.as_mut().iter_mut().filter(|b| self.is_horizon_glyph(**b)) {
+                *byte = 0;
+            }
+        }
+        let rows = lines.len();
+        if rows == 0 {
+            return;
+        }
+        let columns = lines[0].as_mut().len();
+        let width = min(self.width, columns);
+        let first_column = (columns - width) / 2;
+
+        let center_x = (columns * CHAR_WIDTH) as f32 / 2.0;
+        let center_y = (rows * CHAR_HEIGHT) as f32 / 2.0 + pitch * self.pixels_per_degree;
+        let slope = tanf(roll.to_radians());
+        for column in first_column..first_column + width {
+            let x = (column as f32 + 0.5) * CHAR_WIDTH as f32 - center_x;
+            let y = center_y - x * slope;
+            if !y.is_finite() || y < 0.0 {
+                continue;
+            }
+            let row = y as usize / CHAR_HEIGHT;
+            if row >= rows {
+                continue;
+            }
+            let step = (y as usize % CHAR_HEIGHT) / STEP_HEIGHT;
+            lines[row].as_mut()[column] = self.first_glyph + step as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{build_horizon_glyph, HorizonRenderer};
+    use crate::character_memory::{CharData, CHAR_DATA_SIZE};
+
+    #[test]
+    fn test_horizon_glyph() {
+        let mut data: CharData = [0u8; CHAR_DATA_SIZE];
+        build_horizon_glyph(1, &mut data);
+        assert_eq!(data[..6], hex!("55 55 55 55 55 55"));
+        assert_eq!(data[6..12], hex!("AA AA AA 00 00 00"));
+        assert_eq!(data[12..], [0x55u8; CHAR_DATA_SIZE - 12]);
+    }
+
+    #[test]
+    fn test_level() {
+        let mut lines = [[0u8; 30]; 16];
+        let renderer = HorizonRenderer::new(0xC0, 10, 2.0);
+        renderer.render(0.0, 0.0, &mut lines);
+        assert_eq!(lines[8][..10], [0u8; 10]);
+        assert_eq!(lines[8][10..20], [0xC0u8; 10]);
+        assert_eq!(lines[8][20..], [0u8; 10]);
+
+        renderer.render(0.0, 1.0, &mut lines);
+        assert_eq!(lines[8][10..20], [0xC1u8; 10]);
+        assert_eq!(lines.iter().flatten().filter(|&&b| b > 0).count(), 10);
+    }
+
+    #[test]
+    fn test_roll() {
+        let mut lines = [[0u8; 30]; 16];
+        let renderer = HorizonRenderer::new(0xC0, 2, 2.0);
+        renderer.render(45.0, 0.0, &mut lines);
+        assert_eq!(lines[8][14], 0xC3);
+        assert_eq!(lines[7][15], 0xC6);
+    }
+}
@@ -1,7 +1,25 @@
-use peripheral_register::Register;
+use core::cmp::{max, min};
 
-use crate::registers::{DisplayMemoryMode, OperationMode, Registers};
-use crate::{display_memory_address, Attributes, Display};
+use crate::registers::Registers;
+use crate::{display_memory_address, Attributes, Display, COLUMN, ROW};
+
+/// How to handle chars beyond end of row
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Overflow {
+    /// Continue on next row, until end of 16 rows display memory,
+    /// chars wrapped beyond row 13 aren't shown in NTSC
+    Wrap,
+    /// Drop chars beyond end of row
+    Clip,
+    /// Refuse to construct writer
+    Error,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    OutOfScreen,
+    Overflow,
+}
 
 /// Incremental writer starting from given row and column
 /// based on MAX7456 incremental write capability
@@ -10,64 +28,124 @@ pub struct IncrementalWriter<'a> {
     address: u16,
     attributes: Attributes,
     index: usize,
+    end: usize,
 }
 
 impl<'a> IncrementalWriter<'a> {
+    /// Wraps to next row, nothing will be written if out of screen
     pub fn new(bytes: &'a [u8], row: u8, column: u8, attributes: Attributes) -> Self {
-        Self { bytes, address: display_memory_address(row, column), attributes, index: 0 }
+        match Self::try_new(bytes, row, column, attributes, Overflow::Wrap) {
+            Ok(writer) => writer,
+            Err(_) => Self { bytes, address: 0, attributes, index: 0, end: 0 },
+        }
+    }
+
+    pub fn try_new(
+        bytes: &'a [u8],
+        row: u8,
+        column: u8,
+        attributes: Attributes,
+        overflow: Overflow,
+    ) -> Result<Self, Error> {
+        if row as usize >= ROW || column as usize >= COLUMN {
+            return Err(Error::OutOfScreen);
+        }
+        let address = display_memory_address(row, column);
+        let capacity = match overflow {
+            Overflow::Wrap => ROW * COLUMN - address as usize,
+            Overflow::Clip | Overflow::Error => COLUMN - column as usize,
+        };
+        if overflow == Overflow::Error && bytes.len() > capacity {
+            return Err(Error::Overflow);
+        }
+        let end = min(bytes.len(), capacity);
+        Ok(Self { bytes, address, attributes, index: 0, end })
+    }
+
+    /// Limit number of chars to be written, chars already written count
+    pub fn max_length(mut self, length: usize) -> Self {
+        self.end = max(min(self.end, length), self.index);
+        self
     }
 
     pub fn remain(&self) -> usize {
-        self.bytes.len() - self.index
+        self.end - self.index
     }
 
+    /// Since 0xFF terminates auto increment, char 0xFF breaks the run
+    /// and is written with explicit address, returns `None` if nothing left
     pub fn write<'b>(&mut self, buffer: &'b mut [u8]) -> Option<Display<'b>> {
         assert!(buffer.len() >= 10);
 
-        buffer[0] = Registers::DisplayMemoryMode as u8;
-        let mut dmm = Register::<u8, DisplayMemoryMode>::new(0);
-        dmm.set(DisplayMemoryMode::OperationMode, OperationMode::Mode16Bit as u8);
-        dmm.set(
-            DisplayMemoryMode::LocalBackgroundControl,
-            self.attributes.local_background_control as u8,
-        );
-        dmm.set(DisplayMemoryMode::Blink, self.attributes.blink as u8);
-        dmm.set(DisplayMemoryMode::Invert, self.attributes.revert as u8);
-        dmm.set(DisplayMemoryMode::AutoIncrement, 1);
-        buffer[1] = dmm.value;
-
-        buffer[2] = Registers::DisplayMemoryAddressHigh as u8;
-        buffer[3] = (self.address >> 8) as u8;
-        buffer[4] = Registers::DisplayMemoryAddressLow as u8;
-        buffer[5] = self.address as u8;
-
-        let mut offset = 6;
-        let mut written = 0;
-        let mut ff_checker = false;
-        for &byte in self.bytes[self.index..].iter() {
-            buffer[offset] = Registers::DisplayMemoryDataIn as u8;
-            buffer[offset + 1] = byte;
-            ff_checker = byte == 0xFF;
-            written += 1;
-            offset += 2;
-            if offset + 2 >= buffer.len() {
-                break;
+        let length = buffer.len();
+        let mut offset = 0;
+        let mut in_run = false;
+        let mut dmm_written = false;
+        let mut emit = |offset: &mut usize, reg: Registers, value: u8| {
+            buffer[*offset] = reg as u8;
+            buffer[*offset + 1] = value;
+            *offset += 2;
+        };
+        while let Some(&byte) = self.bytes[..self.end].get(self.index) {
+            if byte == 0xFF {
+                let required = in_run as usize * 2 + !dmm_written as usize * 2 + 6;
+                if offset + required > length {
+                    break;
+                }
+                if in_run {
+                    emit(&mut offset, Registers::DisplayMemoryDataIn, 0xFF);
+                    in_run = false;
+                }
+                if !dmm_written {
+                    emit(
+                        &mut offset,
+                        Registers::DisplayMemoryMode,
+                        self.attributes.display_memory_mode(false),
+                    );
+                    dmm_written = true;
+                }
+                emit(&mut offset, Registers::DisplayMemoryAddressHigh, (self.address >> 8) as u8);
+                emit(&mut offset, Registers::DisplayMemoryAddressLow, self.address as u8);
+                emit(&mut offset, Registers::DisplayMemoryDataIn, 0xFF);
+            } else {
+                if !in_run {
+                    if offset + 10 > length {
+                        break;
+                    }
+                    emit(
+                        &mut offset,
+                        Registers::DisplayMemoryMode,
+                        self.attributes.display_memory_mode(true),
+                    );
+                    emit(
+                        &mut offset,
+                        Registers::DisplayMemoryAddressHigh,
+                        (self.address >> 8) as u8,
+                    );
+                    emit(&mut offset, Registers::DisplayMemoryAddressLow, self.address as u8);
+                    in_run = true;
+                    dmm_written = true;
+                } else if offset + 4 > length {
+                    break;
+                }
+                emit(&mut offset, Registers::DisplayMemoryDataIn, byte);
             }
+            self.index += 1;
+            self.address += 1;
         }
-        if ff_checker {
+        if in_run {
+            emit(&mut offset, Registers::DisplayMemoryDataIn, 0xFF);
+        }
+        if offset == 0 {
             return None;
         }
-        buffer[offset] = Registers::DisplayMemoryDataIn as u8;
-        buffer[offset + 1] = 0xFF;
-        self.index += written;
-        self.address += written as u16;
-        Some(Display(&buffer[..offset + 2]))
+        Some(Display(&buffer[..offset]))
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::IncrementalWriter;
+    use super::{Error, IncrementalWriter, Overflow};
 
     #[test]
     fn test_functional() {
@@ -87,7 +165,7 @@ mod test {
              07 46 07 47 07 48 07 49 07 4A 07 4B 07 4C 07 4D 07 FF"
         );
         assert_eq!(writer.write(&mut output).unwrap().0, expected);
-        assert_eq!(writer.remain() > 0, true);
+        assert!(writer.remain() > 0);
         let expected = hex!(
             "04 01 05 00 06 0D 07 4E 07 4F 07 50 07 51 07 52
              07 53 07 54 07 55 07 56 07 57 07 58 07 59 07 5A 07 FF"
@@ -95,4 +173,94 @@ mod test {
         assert_eq!(writer.write(&mut output).unwrap().0, expected);
         assert_eq!(writer.remain(), 0);
     }
+
+    #[test]
+    fn test_0xff_at_start() {
+        let mut output = [0u8; 32];
+        let mut writer = IncrementalWriter::new(b"\xFFa", 0, 0, Default::default());
+        let expected = hex!("04 00 05 00 06 00 07 FF 04 01 05 00 06 01 07 61 07 FF");
+        assert_eq!(writer.write(&mut output).unwrap().0, expected);
+        assert!(writer.write(&mut output).is_none());
+    }
+
+    #[test]
+    fn test_0xff_in_middle() {
+        let mut output = [0u8; 32];
+        let mut writer = IncrementalWriter::new(b"a\xFFb", 0, 0, Default::default());
+        let expected = hex!(
+            "04 01 05 00 06 00 07 61 07 FF 05 00 06 01 07 FF
+             04 01 05 00 06 02 07 62 07 FF"
+        );
+        assert_eq!(writer.write(&mut output).unwrap().0, expected);
+    }
+
+    #[test]
+    fn test_0xff_at_chunk_boundary() {
+        let mut output = [0u8; 12];
+        let mut writer = IncrementalWriter::new(b"ab\xFF", 0, 0, Default::default());
+        let expected = hex!("04 01 05 00 06 00 07 61 07 62 07 FF");
+        assert_eq!(writer.write(&mut output).unwrap().0, expected);
+        let expected = hex!("04 00 05 00 06 02 07 FF");
+        assert_eq!(writer.write(&mut output).unwrap().0, expected);
+        assert_eq!(writer.remain(), 0);
+    }
+
+    #[test]
+    fn test_0xff_displayed() {
+        let mut simulator = crate::simulator::Simulator::new();
+        let mut output = [0u8; 32];
+        let mut writer = IncrementalWriter::new(b"\xFFa\xFF\xFFb", 1, 0, Default::default());
+        while let Some(display) = writer.write(&mut output) {
+            simulator.transaction(display.0);
+        }
+        assert_eq!(simulator.line(1)[..5], *b"\xFFa\xFF\xFFb");
+    }
+
+    #[test]
+    fn test_wrap_until_end_of_screen() {
+        let mut output = [0u8; 32];
+        let mut writer = IncrementalWriter::new(b"abc", 15, 28, Default::default());
+        assert_eq!(writer.remain(), 2);
+        let expected = hex!("04 01 05 01 06 DE 07 61 07 62 07 FF");
+        assert_eq!(writer.write(&mut output).unwrap().0, expected);
+        assert!(writer.write(&mut output).is_none());
+
+        let mut writer = IncrementalWriter::new(b"abc", 16, 0, Default::default());
+        assert!(writer.write(&mut output).is_none());
+    }
+
+    #[test]
+    fn test_clip() {
+        let mut output = [0u8; 32];
+        let mut writer =
+            IncrementalWriter::try_new(b"abc", 0, 28, Default::default(), Overflow::Clip).unwrap();
+        let expected = hex!("04 01 05 00 06 1C 07 61 07 62 07 FF");
+        assert_eq!(writer.write(&mut output).unwrap().0, expected);
+        assert_eq!(writer.remain(), 0);
+    }
+
+    #[test]
+    fn test_error() {
+        let attributes = Default::default();
+        let result = IncrementalWriter::try_new(b"abc", 0, 28, attributes, Overflow::Error);
+        assert_eq!(result.err(), Some(Error::Overflow));
+        let result = IncrementalWriter::try_new(b"abc", 0, 30, attributes, Overflow::Wrap);
+        assert_eq!(result.err(), Some(Error::OutOfScreen));
+        assert!(IncrementalWriter::try_new(b"ab", 0, 28, attributes, Overflow::Error).is_ok());
+    }
+
+    #[test]
+    fn test_max_length() {
+        let mut output = [0u8; 32];
+        let mut writer = IncrementalWriter::new(b"abc", 0, 0, Default::default()).max_length(1);
+        let expected = hex!("04 01 05 00 06 00 07 61 07 FF");
+        assert_eq!(writer.write(&mut output).unwrap().0, expected);
+        assert_eq!(writer.remain(), 0);
+
+        let mut writer = IncrementalWriter::new(b"abc", 0, 0, Default::default()).max_length(2);
+        writer.write(&mut output[..10]).unwrap();
+        assert_eq!(writer.remain(), 1);
+        let writer = writer.max_length(0);
+        assert_eq!(writer.remain(), 0);
+    }
 }
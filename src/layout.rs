@@ -0,0 +1,229 @@
+use core::cmp::{max, min};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::registers::Standard;
+use crate::COLUMN;
+
+pub const NAME_SIZE: usize = 8;
+/// Name, flags, row and column
+pub const ELEMENT_SIZE: usize = NAME_SIZE + 3;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Anchor {
+    TopLeft = 0,
+    Top = 1,
+    TopRight = 2,
+    Left = 3,
+    Center = 4,
+    Right = 5,
+    BottomLeft = 6,
+    Bottom = 7,
+    BottomRight = 8,
+}
+
+impl Anchor {
+    fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => Self::TopLeft,
+            1 => Self::Top,
+            2 => Self::TopRight,
+            3 => Self::Left,
+            4 => Self::Center,
+            5 => Self::Right,
+            6 => Self::BottomLeft,
+            7 => Self::Bottom,
+            8 => Self::BottomRight,
+            _ => return None,
+        })
+    }
+
+    fn origin(&self, rows: usize) -> (isize, isize) {
+        let row = match self {
+            Self::TopLeft | Self::Top | Self::TopRight => 0,
+            Self::Left | Self::Center | Self::Right => rows / 2,
+            Self::BottomLeft | Self::Bottom | Self::BottomRight => rows - 1,
+        };
+        let column = match self {
+            Self::TopLeft | Self::Left | Self::BottomLeft => 0,
+            Self::Top | Self::Center | Self::Bottom => COLUMN / 2,
+            Self::TopRight | Self::Right | Self::BottomRight => COLUMN - 1,
+        };
+        (row as isize, column as isize)
+    }
+}
+
+/// Horizontal alignment of element content relative to its position
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Alignment {
+    Left = 0,
+    Center = 1,
+    Right = 2,
+}
+
+impl Alignment {
+    fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => Self::Left,
+            1 => Self::Center,
+            2 => Self::Right,
+            _ => return None,
+        })
+    }
+}
+
+/// OSD element position relative to an anchor of the screen
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Element {
+    pub name: [u8; NAME_SIZE],
+    pub anchor: Anchor,
+    pub alignment: Alignment,
+    pub row: i8,
+    pub column: i8,
+    pub visible: bool,
+}
+
+impl Element {
+    /// Name longer than `NAME_SIZE` will be truncated
+    pub fn new(name: &str, anchor: Anchor, alignment: Alignment, row: i8, column: i8) -> Self {
+        let mut bytes = [0u8; NAME_SIZE];
+        let length = min(name.len(), NAME_SIZE);
+        bytes[..length].copy_from_slice(&name.as_bytes()[..length]);
+        Self { name: bytes, anchor, alignment, row, column, visible: true }
+    }
+
+    pub fn name(&self) -> &str {
+        let length = self.name.iter().position(|&b| b == 0).unwrap_or(NAME_SIZE);
+        core::str::from_utf8(&self.name[..length]).unwrap_or_default()
+    }
+
+    /// Resolve to (row, column) of first char for content of `width` chars,
+    /// which is clamped within the screen of specified standard
+    pub fn position(&self, standard: Standard, width: usize) -> Option<(u8, u8)> {
+        if !self.visible {
+            return None;
+        }
+        let rows = standard.rows();
+        let (row, column) = self.anchor.origin(rows);
+        let row = row + self.row as isize;
+        let column = column + self.column as isize;
+        let width = min(width, COLUMN) as isize;
+        let column = match self.alignment {
+            Alignment::Left => column,
+            Alignment::Center => column - width / 2,
+            Alignment::Right => column - width + 1,
+        };
+        let row = row.clamp(0, rows as isize - 1);
+        let column = column.clamp(0, COLUMN as isize - max(width, 1));
+        Some((row as u8, column as u8))
+    }
+
+    pub fn to_bytes(&self) -> [u8; ELEMENT_SIZE] {
+        let mut bytes = [0u8; ELEMENT_SIZE];
+        bytes[..NAME_SIZE].copy_from_slice(&self.name);
+        let flags = (self.anchor as u8) << 4 | (self.alignment as u8) << 1 | self.visible as u8;
+        bytes[NAME_SIZE] = flags;
+        bytes[NAME_SIZE + 1] = self.row as u8;
+        bytes[NAME_SIZE + 2] = self.column as u8;
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < ELEMENT_SIZE {
+            return None;
+        }
+        let mut name = [0u8; NAME_SIZE];
+        name.copy_from_slice(&bytes[..NAME_SIZE]);
+        let flags = bytes[NAME_SIZE];
+        Some(Self {
+            name,
+            anchor: Anchor::from_u8(flags >> 4)?,
+            alignment: Alignment::from_u8((flags >> 1) & 0b111)?,
+            row: bytes[NAME_SIZE + 1] as i8,
+            column: bytes[NAME_SIZE + 2] as i8,
+            visible: flags & 1 > 0,
+        })
+    }
+}
+
+/// Collection of named elements, serialized as consecutive elements
+pub struct Layout<'a>(pub &'a [Element]);
+
+impl<'a> Layout<'a> {
+    pub fn find(&self, name: &str) -> Option<&'a Element> {
+        self.0.iter().find(|e| e.name() == name)
+    }
+
+    pub fn serialized_size(&self) -> usize {
+        self.0.len() * ELEMENT_SIZE
+    }
+
+    /// Returns number of bytes written, or `None` if output is too small
+    pub fn serialize(&self, output: &mut [u8]) -> Option<usize> {
+        if output.len() < self.serialized_size() {
+            return None;
+        }
+        for (element, chunk) in self.0.iter().zip(output.chunks_mut(ELEMENT_SIZE)) {
+            chunk.copy_from_slice(&element.to_bytes());
+        }
+        Some(self.serialized_size())
+    }
+
+    /// Returns number of elements deserialized into `output`
+    pub fn deserialize(bytes: &[u8], output: &mut [Element]) -> Option<usize> {
+        let mut count = 0;
+        for (chunk, element) in bytes.chunks_exact(ELEMENT_SIZE).zip(output.iter_mut()) {
+            *element = Element::from_bytes(chunk)?;
+            count += 1;
+        }
+        Some(count)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Alignment, Anchor, Element, Layout, ELEMENT_SIZE};
+    use crate::registers::Standard;
+
+    #[test]
+    fn test_position() {
+        let element = Element::new("battery", Anchor::BottomRight, Alignment::Right, -1, 0);
+        assert_eq!(element.position(Standard::PAL, 4), Some((14, 26)));
+        assert_eq!(element.position(Standard::NTSC, 4), Some((11, 26)));
+
+        let element = Element::new("horizon", Anchor::Center, Alignment::Center, 0, 0);
+        assert_eq!(element.position(Standard::PAL, 10), Some((8, 10)));
+        assert_eq!(element.position(Standard::NTSC, 10), Some((6, 10)));
+    }
+
+    #[test]
+    fn test_clamp_and_visibility() {
+        let mut element = Element::new("rssi", Anchor::TopLeft, Alignment::Center, -2, 0);
+        assert_eq!(element.position(Standard::PAL, 4), Some((0, 0)));
+        let empty = Element::new("empty", Anchor::TopRight, Alignment::Right, 0, 0);
+        assert_eq!(empty.position(Standard::PAL, 0), Some((0, 29)));
+        element.visible = false;
+        assert_eq!(element.position(Standard::PAL, 4), None);
+    }
+
+    #[test]
+    fn test_serialize() {
+        let elements = [
+            Element::new("altitude", Anchor::Left, Alignment::Left, 0, 1),
+            Element::new("speed", Anchor::Right, Alignment::Right, 0, -1),
+        ];
+        let layout = Layout(&elements);
+        let mut bytes = [0u8; ELEMENT_SIZE * 2];
+        assert_eq!(layout.serialize(&mut bytes), Some(ELEMENT_SIZE * 2));
+        assert_eq!(bytes[..ELEMENT_SIZE], hex!("61 6C 74 69 74 75 64 65 31 00 01"));
+
+        let mut output = [Element::new("", Anchor::TopLeft, Alignment::Left, 0, 0); 4];
+        assert_eq!(Layout::deserialize(&bytes, &mut output), Some(2));
+        assert_eq!(output[..2], elements);
+        assert_eq!(Layout(&output).find("speed"), Some(&elements[1]));
+    }
+}
@@ -1,4 +1,4 @@
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 
 #[cfg(test)]
 #[macro_use]
@@ -6,112 +6,423 @@ extern crate hex_literal;
 
 extern crate peripheral_register;
 
+pub mod calibration;
+#[cfg(any(test, feature = "std"))]
+pub mod capture;
 pub mod character_memory;
+pub mod config;
+pub mod diagnostics;
+pub mod disassembler;
+pub mod erase;
+pub mod horizon;
 pub mod incremental_writer;
+pub mod layout;
 pub mod lines_writer;
+pub mod offset;
+pub mod planner;
 pub mod registers;
+#[cfg(any(test, feature = "std"))]
+pub mod render;
+pub mod scheduler;
+#[cfg(any(test, feature = "std"))]
+pub mod simulator;
+pub mod sparse_writer;
+#[cfg(any(test, feature = "std"))]
+pub mod testing;
+pub mod variant;
+pub mod verify;
+pub mod video_loss;
+pub mod vsync;
+mod watchdog;
+
+use core::convert::{Infallible, TryFrom};
 
 use character_memory::{build_store_char_operation, CharData, STORE_CHAR_BUFFER_SIZE};
+use config::{Config, ROW_BRIGHTNESS_REGISTERS};
 use embedded_hal::blocking::delay::{DelayMs, DelayUs};
 use embedded_hal::blocking::spi::{Transfer, Write};
-use embedded_hal::digital::v2::OutputPin;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
 use embedded_hal::spi::{Mode, MODE_3};
+use offset::{HorizontalOffset, VerticalOffset};
 use peripheral_register::Register;
 
 use registers::*;
+use variant::ChipVariant;
+use verify::Verify;
+use watchdog::Shadow;
 
 pub const ROW: usize = 16;
+pub const NTSC_ROW: usize = 13;
 pub const COLUMN: usize = 30;
 
 pub const SPI_MODE: Mode = MODE_3;
 
-pub struct MAX7456<SPI, CS> {
+/// Placeholder for pins not connected
+pub struct NoPin;
+
+impl InputPin for NoPin {
+    type Error = Infallible;
+
+    fn is_high(&self) -> Result<bool, Infallible> {
+        Ok(true)
+    }
+
+    fn is_low(&self) -> Result<bool, Infallible> {
+        Ok(false)
+    }
+}
+
+impl OutputPin for NoPin {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+pub struct MAX7456<SPI, CS, RST = NoPin> {
     spi: SPI,
     cs: CS,
+    reset: Option<RST>,
+    variant: ChipVariant,
     display_enabled: bool,
+    shadow: Shadow,
+    verify: Verify,
 }
 
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub struct Attributes {
     pub local_background_control: bool,
     pub blink: bool,
     pub revert: bool,
 }
 
+impl Attributes {
+    // display memory mode value of 16bit operation mode with these attributes
+    pub(crate) fn display_memory_mode(&self, auto_increment: bool) -> u8 {
+        let mut dmm = Register::<u8, DisplayMemoryMode>::new(0);
+        dmm.set(DisplayMemoryMode::OperationMode, OperationMode::Mode16Bit as u8);
+        dmm.set(DisplayMemoryMode::LocalBackgroundControl, self.local_background_control as u8);
+        dmm.set(DisplayMemoryMode::Blink, self.blink as u8);
+        dmm.set(DisplayMemoryMode::Invert, self.revert as u8);
+        dmm.set(DisplayMemoryMode::AutoIncrement, auto_increment as u8);
+        dmm.value
+    }
+}
+
+impl Standard {
+    pub fn rows(&self) -> usize {
+        match self {
+            Self::NTSC => NTSC_ROW,
+            Self::PAL => ROW,
+        }
+    }
+}
+
+/// Outcome of `check_reset`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ResetCheck {
+    Running,
+    /// Configuration restored, display memory lost and should be resent
+    Restored,
+    /// Reset not done in time or chip not responding, check again later
+    InReset,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Display<'a>(pub &'a [u8]);
 
-impl Default for Attributes {
-    fn default() -> Self {
-        Self { local_background_control: false, blink: false, revert: false }
+impl<SPI, CS, RST> MAX7456<SPI, CS, RST> {
+    fn with(spi: SPI, cs: CS, reset: Option<RST>) -> Self {
+        let (variant, shadow, verify) = Default::default();
+        MAX7456 { spi, cs, reset, variant, display_enabled: false, shadow, verify }
     }
 }
 
-impl<'a, E, PE, SPI, CS> MAX7456<SPI, CS>
+impl<SPI, CS> MAX7456<SPI, CS> {
+    pub fn new(spi: SPI, cs: CS) -> Self {
+        Self::with(spi, cs, None)
+    }
+}
+
+impl<SPI, CS, RST: OutputPin> MAX7456<SPI, CS, RST> {
+    /// `reset` connected to active low RESET pin
+    pub fn with_reset_pin(spi: SPI, cs: CS, reset: RST) -> Self {
+        Self::with(spi, cs, Some(reset))
+    }
+}
+
+impl<E, PE, SPI, CS, RST> MAX7456<SPI, CS, RST>
 where
     SPI: Write<u8, Error = E> + Transfer<u8, Error = E>,
     CS: OutputPin<Error = PE>,
 {
-    pub fn new(spi: SPI, cs: CS) -> Self {
-        MAX7456 { spi, cs, display_enabled: false }
-    }
-
+    /// Reset pin, if any, is dropped, use `release` to get it back
     pub fn free(self) -> (SPI, CS) {
         (self.spi, self.cs)
     }
 
-    pub fn load<T: From<u8>>(&mut self, reg: Registers) -> Result<T, E> {
+    pub fn release(self) -> (SPI, CS, Option<RST>) {
+        (self.spi, self.cs, self.reset)
+    }
+
+    fn read_register(&mut self, address: u8) -> Result<u8, E> {
         let mut value = 0u8;
         self.cs.set_low().ok();
-        self.spi.write(core::slice::from_ref(&reg.read_address()))?;
+        self.spi.write(core::slice::from_ref(&(address | 0x80)))?;
         self.spi.transfer(core::slice::from_mut(&mut value))?;
         self.cs.set_high().ok();
-        Ok(T::from(value))
+        Ok(value)
     }
 
-    pub fn write(&mut self, reg: Registers, value: u8) -> Result<(), E> {
+    fn send(&mut self, address: u8, value: u8) -> Result<(), E> {
         self.cs.set_low().ok();
-        self.spi.write(&[reg as u8, value])?;
+        self.spi.write(&[address, value])?;
         self.cs.set_high().ok();
         Ok(())
     }
 
-    pub fn reset(&mut self, delay: &mut dyn DelayMs<u8>) -> Result<(), E> {
-        let mut video_mode_0: Register<u8, VideoMode0> = Register::of(VideoMode0::SoftwareReset, 1);
-        self.write(Registers::VideoMode0, video_mode_0.value)?;
-        delay.delay_ms(50u8);
-        while video_mode_0.get(VideoMode0::SoftwareReset) > 0 {
-            video_mode_0 = self.load(Registers::VideoMode0)?;
+    fn write_register(&mut self, address: u8, value: u8) -> Result<(), E> {
+        self.send(address, value)?;
+        self.shadow.record(address, value);
+        self.verify_register(address, value)
+    }
+
+    pub fn load<T: From<u8>>(&mut self, reg: Registers) -> Result<T, E> {
+        Ok(T::from(self.read_register(reg as u8)?))
+    }
+
+    pub fn write(&mut self, reg: Registers, value: u8) -> Result<(), E> {
+        self.write_register(reg as u8, value)
+    }
+
+    pub fn read<F: RegisterFields>(&mut self) -> Result<Register<u8, F>, E> {
+        self.load(F::REGISTER)
+    }
+
+    /// Read-modify-write register of field enum `F`
+    pub fn modify<F>(&mut self, modify: impl FnOnce(&mut Register<u8, F>)) -> Result<(), E>
+    where
+        F: WritableRegisterFields,
+    {
+        let mut register = self.read::<F>()?;
+        modify(&mut register);
+        self.write(F::REGISTER, register.value)
+    }
+
+    // polls `done` every millisecond, false if not done within `polls` polls
+    fn poll(
+        &mut self,
+        delay: &mut dyn DelayMs<u8>,
+        polls: u8,
+        mut done: impl FnMut(&mut Self) -> Result<bool, E>,
+    ) -> Result<bool, E> {
+        for _ in 0..polls {
+            if done(self)? {
+                return Ok(true);
+            }
             delay.delay_ms(1u8);
         }
+        Ok(false)
+    }
+
+    fn is_reset_done(&mut self) -> Result<bool, E> {
+        let status = self.read::<Status>()?;
+        Ok(field_value(status.value, Status::ResetMode) == 0)
+    }
+
+    /// Hardware reset if reset pin provided, otherwise software reset,
+    /// returns false if chip not done resetting in time, e.g. not responding
+    pub fn reset(&mut self, delay: &mut dyn DelayMs<u8>) -> Result<bool, E>
+    where
+        RST: OutputPin,
+    {
+        let polls = self.variant.reset_delay_ms();
+        let done = match self.reset.as_mut() {
+            Some(pin) => {
+                pin.set_low().ok();
+                delay.delay_ms(1u8);
+                pin.set_high().ok();
+                self.shadow = Shadow::default();
+                delay.delay_ms(self.variant.reset_delay_ms());
+                self.poll(delay, polls, Self::is_reset_done)?
+            }
+            None => {
+                let video_mode_0: Register<u8, VideoMode0> =
+                    Register::of(VideoMode0::SoftwareReset, 1);
+                self.write(Registers::VideoMode0, video_mode_0.value)?;
+                delay.delay_ms(self.variant.reset_delay_ms());
+                self.poll(delay, polls, |s| {
+                    let video_mode_0 = s.read::<VideoMode0>()?;
+                    Ok(field_value(video_mode_0.value, VideoMode0::SoftwareReset) == 0)
+                })?
+            }
+        };
+        self.display_enabled = false;
+        Ok(done)
+    }
+
+    /// Detects chip reset or brown-out by status or by reading back a configured
+    /// register, then re-applies last written configuration once reset done,
+    /// waiting for it no longer than a reset normally takes
+    pub fn check_reset(&mut self, delay: &mut dyn DelayMs<u8>) -> Result<ResetCheck, E> {
+        let status = self.read::<Status>()?;
+        let mut in_reset = field_value(status.value, Status::ResetMode) > 0;
+        if !in_reset {
+            if let Some((address, value)) = self.shadow.probe() {
+                in_reset = self.read_register(address)? != value;
+            }
+        }
+        if !in_reset {
+            return Ok(ResetCheck::Running);
+        }
+        if !self.poll(delay, self.variant.reset_delay_ms(), Self::is_reset_done)? {
+            return Ok(ResetCheck::InReset);
+        }
+        let shadow = self.shadow;
+        for (address, value) in shadow.restore_sequence() {
+            self.write_register(address, value)?;
+        }
+        Ok(ResetCheck::Restored)
+    }
+
+    /// Writes whole configuration, display enabled last
+    pub fn apply(&mut self, config: &Config) -> Result<(), E> {
+        let disabled = Config { display_enabled: false, ..*config };
+        self.write(Registers::VideoMode0, disabled.video_mode_0())?;
+        self.write(Registers::VideoMode1, config.video_mode_1())?;
+        self.set_horizontal_offset_to(config.horizontal_offset)?;
+        self.set_vertical_offset_to(config.vertical_offset)?;
+        for (row, &value) in config.row_brightness.iter().enumerate() {
+            self.write(Registers::row_brightness(row).unwrap(), value)?;
+        }
+        self.write(Registers::OSDInsertionMux, config.insertion_mux())?;
+        let black_level = self.load(Registers::OSDBlackLevel)?;
+        self.write(Registers::OSDBlackLevel, config.black_level(black_level))?;
+        if config.display_enabled {
+            self.write(Registers::VideoMode0, config.video_mode_0())?;
+        }
+        self.display_enabled = config.display_enabled;
         Ok(())
     }
 
+    pub fn read_config(&mut self) -> Result<Config, E> {
+        let mut config = Config::default();
+        config.set_video_mode_0(self.load(Registers::VideoMode0)?);
+        config.set_video_mode_1(self.load(Registers::VideoMode1)?);
+        config.horizontal_offset = self.horizontal_offset()?;
+        config.vertical_offset = self.vertical_offset()?;
+        for row in 0..ROW_BRIGHTNESS_REGISTERS {
+            config.row_brightness[row] = self.load(Registers::row_brightness(row).unwrap())?;
+        }
+        config.set_insertion_mux(self.load(Registers::OSDInsertionMux)?);
+        config.set_black_level(self.load(Registers::OSDBlackLevel)?);
+        Ok(config)
+    }
+
     pub fn enable_display(&mut self, enable: bool) -> Result<(), E> {
-        let mut video_mode_0: Register<u8, VideoMode0> = self.load(Registers::VideoMode0)?;
-        video_mode_0.set(VideoMode0::EnableDisplay, enable as u8);
-        self.write(Registers::VideoMode0, video_mode_0.value)
+        self.modify::<VideoMode0>(|r| r.set(VideoMode0::EnableDisplay, enable as u8))?;
+        self.display_enabled = enable;
+        Ok(())
     }
 
     pub fn set_standard(&mut self, standard: Standard) -> Result<(), E> {
-        let mut video_mode_0: Register<u8, VideoMode0> = self.load(Registers::VideoMode0)?;
-        video_mode_0.set(VideoMode0::Standard, standard as u8);
-        self.write(Registers::VideoMode0, video_mode_0.value)
+        self.modify::<VideoMode0>(|r| r.set(VideoMode0::Standard, standard as u8))
     }
 
     pub fn set_sync_mode(&mut self, sync_mode: SyncMode) -> Result<(), E> {
-        let mut video_mode_0: Register<u8, VideoMode0> = self.load(Registers::VideoMode0)?;
-        video_mode_0.set(VideoMode0::SyncMode, sync_mode as u8);
-        self.write(Registers::VideoMode0, video_mode_0.value)
+        self.modify::<VideoMode0>(|r| r.set(VideoMode0::SyncMode, sync_mode as u8))
+    }
+
+    /// Whether VM0 changes take effect immediately or on next VSYNC
+    pub fn set_vertical_sync(&mut self, vertical_sync: VerticalSync) -> Result<(), E> {
+        self.modify::<VideoMode0>(|r| r.set(VideoMode0::VerticalSync, vertical_sync as u8))
     }
 
+    #[deprecated(note = "use set_horizontal_offset_to")]
     pub fn set_horizental_offset(&mut self, offset: i8) -> Result<(), E> {
-        // -32 ~ +31
-        self.write(Registers::HorizentalOffset, (offset + 32) as u8)
+        self.set_horizontal_offset_to(HorizontalOffset::saturating(offset))
     }
 
+    pub fn set_horizontal_offset_to(&mut self, offset: HorizontalOffset) -> Result<(), E> {
+        self.write(Registers::HorizentalOffset, offset.register_value())
+    }
+
+    pub fn horizontal_offset(&mut self) -> Result<HorizontalOffset, E> {
+        let value = self.load(Registers::HorizentalOffset)?;
+        Ok(HorizontalOffset::from_register_value(value))
+    }
+
+    #[deprecated(note = "use set_vertical_offset_to")]
     pub fn set_vertical_offset(&mut self, offset: i8) -> Result<(), E> {
-        // -16 ~ +15
-        self.write(Registers::VerticalOffset, (offset + 16) as u8)
+        self.set_vertical_offset_to(VerticalOffset::saturating(offset))
+    }
+
+    pub fn set_vertical_offset_to(&mut self, offset: VerticalOffset) -> Result<(), E> {
+        self.write(Registers::VerticalOffset, offset.register_value())
+    }
+
+    pub fn vertical_offset(&mut self) -> Result<VerticalOffset, E> {
+        let value = self.load(Registers::VerticalOffset)?;
+        Ok(VerticalOffset::from_register_value(value))
+    }
+
+    /// Returns false if `row` beyond 16 rows
+    pub fn set_row_brightness(
+        &mut self,
+        row: usize,
+        black: CharacterBlackLevel,
+        white: CharacterWhiteLevel,
+    ) -> Result<bool, E> {
+        let reg = match Registers::row_brightness(row) {
+            Some(reg) => reg,
+            None => return Ok(false),
+        };
+        let mut value: Register<u8, RowBrightness> = Register::new(0);
+        value.set(RowBrightness::CharacterBlackLevel, black as u8);
+        value.set(RowBrightness::CharacterWhiteLevel, white as u8);
+        self.write(reg, value.value)?;
+        Ok(true)
+    }
+
+    pub fn row_brightness(
+        &mut self,
+        row: usize,
+    ) -> Result<Option<(CharacterBlackLevel, CharacterWhiteLevel)>, E> {
+        let reg = match Registers::row_brightness(row) {
+            Some(reg) => reg,
+            None => return Ok(None),
+        };
+        let value: u8 = self.load(reg)?;
+        let black = field_value(value, RowBrightness::CharacterBlackLevel);
+        let white = field_value(value, RowBrightness::CharacterWhiteLevel);
+        let black = CharacterBlackLevel::try_from(black).unwrap();
+        Ok(Some((black, CharacterWhiteLevel::try_from(white).unwrap())))
+    }
+
+    /// Levels above 15 saturate. Datasheet asks to keep factory preset level bits,
+    /// `Auto` does so, while manual level has to overwrite them as cameras with
+    /// poor black level need it. Being a register not NVM, power cycle restores them
+    pub fn set_black_level(&mut self, black_level: BlackLevel) -> Result<(), E> {
+        self.modify::<OSDBlackLevel>(|r| match black_level {
+            BlackLevel::Auto => r.set(OSDBlackLevel::AutoBlackLevelDisable, 0),
+            BlackLevel::Manual(level) => {
+                r.set(OSDBlackLevel::AutoBlackLevelDisable, 1);
+                r.set(OSDBlackLevel::Level, core::cmp::min(level, 15));
+            }
+        })
+    }
+
+    pub fn black_level(&mut self) -> Result<BlackLevel, E> {
+        let value = self.read::<OSDBlackLevel>()?.value;
+        if field_value(value, OSDBlackLevel::AutoBlackLevelDisable) == 0 {
+            return Ok(BlackLevel::Auto);
+        }
+        Ok(BlackLevel::Manual(field_value(value, OSDBlackLevel::Level)))
     }
 
     pub fn start_clear_display(&mut self) -> Result<(), E> {
@@ -119,13 +430,32 @@ where
         self.write(Registers::DisplayMemoryMode, dmm.value)
     }
 
+    /// Clear display memory at next VSYNC instead of immediately
+    pub fn start_clear_display_on_vsync(&mut self) -> Result<(), E> {
+        let mut dmm: Register<u8, DisplayMemoryMode> = Register::of(DisplayMemoryMode::Clear, 1);
+        dmm.set(DisplayMemoryMode::VerticalSyncClear, 1);
+        self.write(Registers::DisplayMemoryMode, dmm.value)
+    }
+
+    /// Whether no sync detected on video input
+    pub fn is_sync_lost(&mut self) -> Result<bool, E> {
+        let status = self.read::<Status>()?;
+        Ok(field_value(status.value, Status::LossOfSync) > 0)
+    }
+
+    /// Whether VSYNC output is active, i.e. within vertical blanking
+    pub fn is_in_vsync(&mut self) -> Result<bool, E> {
+        let status = self.read::<Status>()?;
+        Ok(field_value(status.value, Status::VSyncOutputLevel) == 0)
+    }
+
     pub fn is_display_enabled(&mut self) -> bool {
         self.display_enabled
     }
 
     pub fn is_display_cleared(&mut self) -> Result<bool, E> {
-        let dmm: Register<u8, DisplayMemoryMode> = self.load(Registers::DisplayMemoryMode)?;
-        Ok(dmm.get(DisplayMemoryMode::Clear) == 0)
+        let dmm = self.read::<DisplayMemoryMode>()?;
+        Ok(field_value(dmm.value, DisplayMemoryMode::Clear) == 0)
     }
 
     pub fn wait_clear_display(&mut self, delay: &mut dyn DelayUs<u8>) -> Result<(), E> {
@@ -147,13 +477,14 @@ where
             CharacterMemoryMode::ReadFromNVM as u8,
         ])?;
         self.cs.set_high().ok();
-        for i in 0..64 {
+        for (i, byte) in output.iter_mut().enumerate() {
             self.write(Registers::CharacterMemoryAddressLow, i as u8)?;
-            output[i] = self.load(Registers::CharacterMemoryDataOut)?;
+            *byte = self.load(Registers::CharacterMemoryDataOut)?;
         }
         Ok(true)
     }
 
+    /// Returns false if display enabled or NVM write not done in time
     pub fn store_char(
         &mut self,
         index: u8,
@@ -165,26 +496,131 @@ where
         }
         let mut transaction = [0u8; STORE_CHAR_BUFFER_SIZE];
         build_store_char_operation(data, index, &mut transaction);
+        self.cs.set_low().ok();
         self.spi.write(&transaction)?;
-        delay.delay_ms(12);
-        loop {
-            let status: Register<u8, Status> = self.load(Registers::Status)?;
-            let status = status.get(Status::CharacterMemoryStatus);
-            if status == CharacterMemoryStatus::Available as u8 {
-                break;
-            }
-        }
-        Ok(true)
+        self.cs.set_high().ok();
+        delay.delay_ms(self.variant.store_char_delay_ms());
+        self.poll(delay, self.variant.store_char_delay_ms(), |s| {
+            let status = s.read::<Status>()?;
+            let status = field_value(status.value, Status::CharacterMemoryStatus);
+            Ok(status == CharacterMemoryStatus::Available as u8)
+        })
     }
 
     pub fn write_display(&mut self, display: &Display) -> Result<(), E> {
         self.cs.set_low().ok();
-        self.spi.write(&display.0)?;
+        self.spi.write(display.0)?;
         self.cs.set_high().ok();
-        Ok(())
+        self.verify_display(display)
     }
 }
 
 #[cfg(test)]
 #[macro_use]
 extern crate std;
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::convert::Infallible;
+
+    use embedded_hal::digital::v2::OutputPin;
+
+    use crate::registers::{field_value, BlackLevel, VideoMode1};
+    use crate::registers::{CharacterBlackLevel, CharacterWhiteLevel, Registers};
+    use crate::simulator::Simulator;
+    use crate::testing::{MockDelay, Recorder};
+    use crate::MAX7456;
+
+    #[test]
+    fn test_hardware_reset_timeout() {
+        let recorder = Recorder::new();
+        let levels = RefCell::new(Vec::new());
+        let mut max7456 = MAX7456::with_reset_pin(recorder.spi(), recorder.cs(), ResetPin(&levels));
+        recorder.respond(&[0xFF; 50]); // stuck in reset
+        let mut delay = MockDelay::default();
+        assert!(!max7456.reset(&mut delay).unwrap());
+        assert_eq!(delay.elapsed_us, 1000 + 50_000 + 50_000);
+    }
+
+    #[test]
+    fn test_not_responding() {
+        let recorder = Recorder::new();
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        recorder.respond(&[0xFF; 50]); // MISO floating high
+        let mut delay = MockDelay::default();
+        assert!(!max7456.reset(&mut delay).unwrap());
+        assert_eq!(delay.elapsed_us, 100_000);
+
+        recorder.respond(&[0xFF; 12]);
+        let mut delay = MockDelay::default();
+        assert!(!max7456.store_char(0, &[0u8; 64], &mut delay).unwrap());
+        assert_eq!(delay.elapsed_us, 24_000);
+    }
+
+    #[test]
+    fn test_modify() {
+        let recorder = Recorder::with_simulator(Simulator::new());
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        max7456.modify::<VideoMode1>(|r| r.set(VideoMode1::BlinkTime, 3)).unwrap();
+        recorder.expect(&[&[0x81, 0x00], &[0x01, 0x4F]]);
+        let vm1 = max7456.read::<VideoMode1>().unwrap();
+        assert_eq!(field_value(vm1.value, VideoMode1::Brightness), 4);
+    }
+
+    #[test]
+    fn test_black_level() {
+        let mut simulator = Simulator::new();
+        simulator.transaction(&[0x6C, 0x05]); // factory preset
+        let recorder = Recorder::with_simulator(simulator);
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        assert_eq!(max7456.black_level().unwrap(), BlackLevel::Auto);
+        max7456.set_black_level(BlackLevel::Manual(20)).unwrap();
+        assert_eq!(max7456.black_level().unwrap(), BlackLevel::Manual(15));
+        max7456.set_black_level(BlackLevel::Auto).unwrap();
+        let osdbl = recorder.simulator(|s| s.register(Registers::OSDBlackLevel)).unwrap();
+        assert_eq!(osdbl, 0x0F);
+    }
+
+    #[test]
+    fn test_row_brightness() {
+        let recorder = Recorder::with_simulator(Simulator::new());
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        let (black, white) = (CharacterBlackLevel::Percent20, CharacterWhiteLevel::Percent80);
+        assert!(max7456.set_row_brightness(15, black, white).unwrap());
+        assert!(!max7456.set_row_brightness(16, black, white).unwrap());
+        recorder.expect(&[&[0x1F, 0x0B]]);
+        assert_eq!(max7456.row_brightness(15).unwrap(), Some((black, white)));
+        let default = (CharacterBlackLevel::Percent0, CharacterWhiteLevel::Percent100);
+        assert_eq!(max7456.row_brightness(0).unwrap(), Some(default));
+    }
+
+    struct ResetPin<'a>(&'a RefCell<Vec<bool>>);
+
+    impl<'a> OutputPin for ResetPin<'a> {
+        type Error = Infallible;
+
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            self.0.borrow_mut().push(false);
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            self.0.borrow_mut().push(true);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_hardware_reset() {
+        let recorder = Recorder::new();
+        let levels = RefCell::new(Vec::new());
+        let mut max7456 = MAX7456::with_reset_pin(recorder.spi(), recorder.cs(), ResetPin(&levels));
+        recorder.respond(&[0x40, 0x00]); // in reset, reset done
+        let mut delay = MockDelay::default();
+        assert!(max7456.reset(&mut delay).unwrap());
+        assert_eq!(*levels.borrow(), [false, true]);
+        recorder.expect(&[&[0xA0, 0x00], &[0xA0, 0x00]]);
+        assert_eq!(delay.elapsed_us, 52_000);
+    }
+}
@@ -2,22 +2,31 @@ use core::cmp::min;
 
 use peripheral_register::Register;
 
+use crate::erase::erase;
 use crate::registers::{DisplayMemoryMode, OperationMode, Registers};
 use crate::{Attributes, Display, COLUMN, ROW};
 
 const MAX_ADDRESS: u16 = (ROW * COLUMN) as u16;
 
 /// Full lines writer which write every char with specified row and column
-/// `null` chars will be ignored
+/// `null` chars will be ignored unless another skip sentinel specified
 pub struct LinesWriter<'a, T> {
     lines: &'a [T],
     attributes: Attributes,
     address: u16,
+    skip: Option<u8>,
 }
 
 impl<'a, T: AsRef<[u8]>> LinesWriter<'a, T> {
     pub fn new(lines: &'a [T], attributes: Attributes) -> Self {
-        Self { lines, attributes, address: 0 }
+        Self { lines, attributes, address: 0, skip: Some(0) }
+    }
+
+    /// Cells holding `skip` are left untouched while every other byte is written,
+    /// so that a cell can be erased with blank glyph, `None` to write every cell
+    pub fn skip(mut self, skip: Option<u8>) -> Self {
+        self.skip = skip;
+        self
     }
 
     fn dump_bytes(&mut self, limit: u16, buffer: &mut [u8]) -> usize {
@@ -32,7 +41,7 @@ impl<'a, T: AsRef<[u8]>> LinesWriter<'a, T> {
                 continue;
             }
             let byte = self.lines[row].as_ref()[column];
-            if byte == 0 {
+            if Some(byte) == self.skip {
                 self.address += 1;
                 continue;
             }
@@ -46,7 +55,7 @@ impl<'a, T: AsRef<[u8]>> LinesWriter<'a, T> {
                 break;
             }
         }
-        return offset;
+        offset
     }
 
     pub fn write<'b>(&mut self, buffer: &'b mut [u8]) -> Display<'b> {
@@ -81,27 +90,16 @@ impl<'a, T: AsRef<[u8]>> LinesWriter<'a, T> {
             offset += length + 2;
         }
         if offset < buffer.len() {
-            buffer[offset] = 0; // in case of revert
+            buffer[offset] = 0; // in case of erase
         }
 
         Display(&buffer[..if offset > 2 { offset } else { 0 }])
     }
 }
 
-pub fn revert(buffer: &mut [u8]) -> Display {
-    if buffer[0] != Registers::DisplayMemoryMode as u8 {
-        return Display(&buffer[..0]);
-    }
-    for i in 1..buffer.len() / 2 {
-        match buffer[i * 2] {
-            b if b == Registers::DisplayMemoryDataIn as u8 => buffer[i * 2 + 1] = 0u8,
-            b if b == Registers::CharacterMemoryAddressLow as u8 => continue,
-            b if b == Registers::CharacterMemoryAddressHigh as u8 => continue,
-            0 => return Display(&buffer[..(i - 1) * 2]),
-            _ => return Display(&buffer[..0]),
-        }
-    }
-    Display(buffer)
+#[deprecated(note = "use `erase::erase` instead")]
+pub fn revert(buffer: &mut [u8]) -> Display<'_> {
+    erase(buffer, 0)
 }
 
 #[cfg(test)]
@@ -112,7 +110,7 @@ mod test {
     fn test_low_address() {
         let mut output = [0u8; 32];
         let mut lines = [[0u8; 30]; 16];
-        lines[7][29] = 't' as u8;
+        lines[7][29] = b't';
         let mut writer = LinesWriter::new(&lines, Default::default());
         let expected = hex!("04 00 05 00 06 EF 07 74");
         assert_eq!(writer.write(&mut output).0, expected);
@@ -122,7 +120,7 @@ mod test {
     fn test_high_address() {
         let mut output = [0u8; 32];
         let mut lines = [[0u8; 30]; 16];
-        lines[8][29] = 't' as u8;
+        lines[8][29] = b't';
         let mut writer = LinesWriter::new(&lines, Default::default());
         let expected = hex!("04 00 05 01 06 0D 07 74");
         assert_eq!(writer.write(&mut output).0, expected);
@@ -132,8 +130,8 @@ mod test {
     fn test_within_addreess() {
         let mut output = [0u8; 32];
         let mut lines = [[0u8; 30]; 16];
-        lines[7][29] = 't' as u8;
-        lines[8][15] = 't' as u8;
+        lines[7][29] = b't';
+        lines[8][15] = b't';
         let mut writer = LinesWriter::new(&lines, Default::default());
         let expected = hex!("04 00 05 00 06 EF 07 74 06 FF 07 74");
         assert_eq!(writer.write(&mut output).0, expected);
@@ -143,8 +141,8 @@ mod test {
     fn test_cross_address() {
         let mut output = [0u8; 32];
         let mut lines = [[0u8; 30]; 16];
-        lines[7][29] = 't' as u8;
-        lines[8][29] = 't' as u8;
+        lines[7][29] = b't';
+        lines[8][29] = b't';
         let mut writer = LinesWriter::new(&lines, Default::default());
         let expected = hex!("04 00 05 00 06 EF 07 74 05 01 06 0D 07 74");
         assert_eq!(writer.write(&mut output).0, expected);
@@ -154,8 +152,8 @@ mod test {
     fn test_exactly_one_buffer() {
         let mut output = [0u8; 14];
         let mut lines = [[0u8; 30]; 16];
-        lines[7][29] = 't' as u8;
-        lines[8][29] = 't' as u8;
+        lines[7][29] = b't';
+        lines[8][29] = b't';
         let mut writer = LinesWriter::new(&lines, Default::default());
         let expected = hex!("04 00 05 00 06 EF 07 74 05 01 06 0D 07 74");
         assert_eq!(writer.write(&mut output).0, expected);
@@ -166,8 +164,8 @@ mod test {
     fn test_multiple_buffer() {
         let mut output = [0u8; 8];
         let mut lines = [[0u8; 30]; 16];
-        lines[7][29] = 't' as u8;
-        lines[8][29] = 't' as u8;
+        lines[7][29] = b't';
+        lines[8][29] = b't';
         let mut writer = LinesWriter::new(&lines, Default::default());
         let expected = hex!("04 00 05 00 06 EF 07 74");
         assert_eq!(writer.write(&mut output).0, expected);
@@ -180,8 +178,8 @@ mod test {
     fn test_non_standard_screen() {
         let mut output = [0u8; 8];
         let mut lines = [[0u8; 29]; 15];
-        lines[7][28] = 't' as u8;
-        lines[8][28] = 't' as u8;
+        lines[7][28] = b't';
+        lines[8][28] = b't';
         let mut writer = LinesWriter::new(&lines, Default::default());
         let expected = hex!("04 00 05 00 06 EE 07 74");
         assert_eq!(writer.write(&mut output).0, expected);
@@ -189,4 +187,34 @@ mod test {
         let expected = hex!("04 00 05 01 06 0C 07 74");
         assert_eq!(writer.write(&mut output).0, expected);
     }
+
+    #[test]
+    fn test_skip_sentinel() {
+        let mut output = [0u8; 32];
+        let mut lines = [[0xFEu8; 30]; 16];
+        lines[0][0] = 0;
+        lines[0][1] = b' ';
+        let mut writer = LinesWriter::new(&lines, Default::default()).skip(Some(0xFE));
+        let expected = hex!("04 00 05 00 06 00 07 00 06 01 07 20");
+        assert_eq!(writer.write(&mut output).0, expected);
+
+        let lines = [[0u8; 2]; 1];
+        let mut writer = LinesWriter::new(&lines, Default::default()).skip(None);
+        let expected = hex!("04 00 05 00 06 00 07 00 06 01 07 00");
+        assert_eq!(writer.write(&mut output).0, expected);
+    }
+
+    #[test]
+    fn test_erase_stale_glyph() {
+        let mut simulator = crate::simulator::Simulator::new();
+        let mut output = [0u8; 64];
+        let mut lines = [[0xFEu8; 30]; 16];
+        lines[0][..4].copy_from_slice(b"100%");
+        let mut writer = LinesWriter::new(&lines, Default::default()).skip(Some(0xFE));
+        simulator.transaction(writer.write(&mut output).0);
+        lines[0][..4].copy_from_slice(b"99% ");
+        let mut writer = LinesWriter::new(&lines, Default::default()).skip(Some(0xFE));
+        simulator.transaction(writer.write(&mut output).0);
+        assert_eq!(simulator.line(0)[..5], *b"99% \0");
+    }
 }
@@ -0,0 +1,69 @@
+use core::cmp::{max, min};
+
+/// Display position offset in pixels, positive to the right, -32 ~ +31
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct HorizontalOffset(i8);
+
+/// Display position offset in pixels, positive downwards, -16 ~ +15
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct VerticalOffset(i8);
+
+macro_rules! offset {
+    ($type:ident, $min:expr, $max:expr) => {
+        impl $type {
+            pub const MIN: i8 = $min;
+            pub const MAX: i8 = $max;
+
+            /// `None` if out of range
+            pub fn new(pixels: i8) -> Option<Self> {
+                match pixels {
+                    $min..=$max => Some(Self(pixels)),
+                    _ => None,
+                }
+            }
+
+            pub fn saturating(pixels: i8) -> Self {
+                Self(min(max(pixels, Self::MIN), Self::MAX))
+            }
+
+            pub fn pixels(&self) -> i8 {
+                self.0
+            }
+
+            /// `None` if out of range
+            pub fn checked_add(&self, pixels: i8) -> Option<Self> {
+                self.0.checked_add(pixels).and_then(Self::new)
+            }
+
+            pub(crate) fn register_value(&self) -> u8 {
+                (self.0 - Self::MIN) as u8
+            }
+
+            /// Reserved bits ignored
+            pub(crate) fn from_register_value(value: u8) -> Self {
+                let mask = (Self::MAX - Self::MIN) as u8;
+                Self((value & mask) as i8 + Self::MIN)
+            }
+        }
+    };
+}
+
+offset!(HorizontalOffset, -32, 31);
+offset!(VerticalOffset, -16, 15);
+
+#[cfg(test)]
+mod test {
+    use super::{HorizontalOffset, VerticalOffset};
+
+    #[test]
+    fn test_range() {
+        assert_eq!(HorizontalOffset::new(32), None);
+        assert_eq!(HorizontalOffset::new(-32).unwrap().register_value(), 0);
+        assert_eq!(HorizontalOffset::new(31).unwrap().register_value(), 0x3F);
+        assert_eq!(VerticalOffset::new(-17), None);
+        assert_eq!(VerticalOffset::saturating(i8::MAX).pixels(), 15);
+        assert_eq!(VerticalOffset::saturating(i8::MIN).register_value(), 0);
+        assert_eq!(VerticalOffset::default().checked_add(16), None);
+        assert_eq!(HorizontalOffset::from_register_value(0xE1).pixels(), 1);
+    }
+}
@@ -0,0 +1,149 @@
+use core::cmp::min;
+use core::time::Duration;
+
+use crate::sparse_writer::Cell;
+
+// DMM, DMAH, DMAL and 0xFF terminator
+const RUN_OVERHEAD: usize = 8;
+const RUN_BYTES_PER_CELL: usize = 2;
+// DMAL and DMDI
+const ADDRESSED_BYTES_PER_CELL: usize = 4;
+const REGISTER_WRITE: usize = 2;
+
+/// Planned decision of a cell, for each state whether
+/// display memory address high is known before writing the cell
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub(crate) struct Plan {
+    cost: [u16; 2],
+    run: [u16; 2],
+}
+
+impl Plan {
+    /// Auto increment run length starting from this cell, 0 for addressed write
+    pub(crate) fn run(&self, high_known: bool) -> usize {
+        self.run[high_known as usize] as usize
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Estimate {
+    pub bytes: usize,
+}
+
+impl Estimate {
+    /// 8 clocks per byte, gaps between bytes not included,
+    /// `Duration::MAX` if clock is 0
+    pub fn transfer_time(&self, spi_clock_hz: u32) -> Duration {
+        let nanos = (self.bytes as u64 * 8 * 1_000_000_000).checked_div(spi_clock_hz as u64);
+        nanos.map(Duration::from_nanos).unwrap_or(Duration::MAX)
+    }
+}
+
+fn run_length(cells: &[Cell], index: usize) -> usize {
+    let first = &cells[index];
+    let contiguous = cells[index..].iter().enumerate().take_while(|(i, cell)| {
+        cell.address == first.address + *i as u16
+            && cell.attributes == first.attributes
+            && cell.byte != 0xFF
+    });
+    contiguous.count()
+}
+
+/// Choose between addressed write and auto increment run for every cell,
+/// minimizing total SPI bytes, cells must be sorted by address
+pub(crate) fn plan(cells: &mut [Cell]) -> Estimate {
+    let cost = |cells: &[Cell], index: usize, high_known: bool| match cells.get(index) {
+        Some(cell) => cell.plan.cost[high_known as usize] as usize,
+        None => 0,
+    };
+    for i in (0..cells.len()).rev() {
+        let cell = cells[i];
+        let previous = if i > 0 { Some(cells[i - 1]) } else { None };
+        let dmm_cost = match previous {
+            Some(previous) if previous.attributes == cell.attributes => 0,
+            _ => REGISTER_WRITE,
+        };
+        let high = cell.address >> 8;
+        let high_changed = previous.map(|p| p.address >> 8 != high).unwrap_or(true);
+        let max_run = run_length(cells, i);
+        for high_known in [false, true].iter().copied() {
+            let high_cost = if !high_known || high_changed { REGISTER_WRITE } else { 0 };
+            let mut best =
+                dmm_cost + high_cost + ADDRESSED_BYTES_PER_CELL + cost(cells, i + 1, true);
+            let mut best_run = 0;
+            for run in 2..=max_run {
+                let total = RUN_OVERHEAD + RUN_BYTES_PER_CELL * run + cost(cells, i + run, false);
+                if total < best {
+                    best = total;
+                    best_run = run;
+                }
+            }
+            let plan = &mut cells[i].plan;
+            plan.cost[high_known as usize] = min(best, u16::MAX as usize) as u16;
+            plan.run[high_known as usize] = best_run as u16;
+        }
+    }
+    Estimate { bytes: cost(cells, 0, false) }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Estimate;
+    use crate::lines_writer::LinesWriter;
+    use crate::simulator::Simulator;
+    use crate::sparse_writer::{Cell, SparseWriter};
+    use crate::Attributes;
+    use core::time::Duration;
+
+    #[test]
+    fn test_choice() {
+        let mut storage = [Cell::default(); 16];
+        let none = Attributes::default();
+        // two cells are cheaper addressed, five cells cheaper in a run
+        let cells = [(0, 0), (0, 1), (1, 0), (1, 1), (1, 2), (1, 3), (1, 4)];
+        let cells = cells.iter().map(|&(row, column)| (row, column, b'a', none));
+        let mut writer = SparseWriter::new(cells, &mut storage);
+        assert_eq!(writer.estimate().bytes, 2 + 2 + 4 * 2 + 8 + 2 * 5);
+        let mut output = [0u8; 64];
+        let expected = hex!(
+            "04 00 05 00 06 00 07 61 06 01 07 61
+             04 01 05 00 06 1E 07 61 07 61 07 61 07 61 07 61 07 FF"
+        );
+        assert_eq!(writer.write(&mut output).0, expected);
+    }
+
+    #[test]
+    fn test_full_screen() {
+        let mut lines = [[b'a'; 30]; 16];
+        lines[3][4] = 0;
+        let mut storage = [Cell::default(); 480];
+        let mut writer =
+            SparseWriter::from_lines(&lines, Default::default(), Some(0), &mut storage);
+        assert_eq!(writer.estimate().bytes, (8 + 2 * 94) + (8 + 2 * 385));
+
+        let mut output = [0u8; 1024];
+        let length = writer.write(&mut output).0.len();
+        assert_eq!(length, writer.estimate().bytes);
+        let mut simulator = Simulator::new();
+        simulator.transaction(&output[..length]);
+        assert_eq!(simulator.display_memory().iter().filter(|&&b| b == b'a').count(), 479);
+
+        let mut writer = LinesWriter::new(&lines, Default::default());
+        let mut total = 0;
+        loop {
+            let display = writer.write(&mut output);
+            if display.0.is_empty() {
+                break;
+            }
+            total += display.0.len();
+        }
+        assert!(total > 4 * 479);
+    }
+
+    #[test]
+    fn test_transfer_time() {
+        let estimate = Estimate { bytes: 1000 };
+        assert_eq!(estimate.transfer_time(10_000_000), Duration::from_micros(800));
+        assert_eq!(estimate.transfer_time(0), Duration::MAX);
+    }
+}
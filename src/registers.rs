@@ -1,3 +1,5 @@
+use core::convert::TryFrom;
+
 use peripheral_register::{register_fields, Field};
 
 #[macro_export]
@@ -28,17 +30,20 @@ macro_rules! brightness {
     };
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum SyncMode {
     AutoSyncDetect = 0,
     External = 0b10,
     Internal = 0b11,
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum VerticalSync {
     Immediately = 0,
     NextVSync = 1,
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Standard {
     NTSC = 0,
     PAL = 1,
@@ -67,15 +72,16 @@ register_fields! {
 register_fields! {
     #[derive(Debug)]
     enum HorizentalOffset {
-        Offset = 0: 5, // unit pixel
+        Offset = 0: 6, // unit pixel
     }
 
     #[derive(Debug)]
     enum VerticalOffset {
-        Offset = 0: 4, // unit pixel
+        Offset = 0: 5, // unit pixel
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum OperationMode {
     Mode16Bit = 0, //
     Mode8Bit = 1,
@@ -107,6 +113,23 @@ register_fields! {
     }
 }
 
+register_fields! {
+    // attribute byte of display memory, accessed in 8bit operation mode
+    #[derive(Debug)]
+    pub enum CharacterAttribute {
+        LocalBackgroundControl = 2: 1,
+        Blink = 1: 1,
+        Invert = 0: 1,
+    }
+}
+
+/// Extract field value from raw register value, use instead of
+/// `Register::get` which masks with unshifted mask
+pub fn field_value<F: Field>(value: u8, field: F) -> u8 {
+    let (offset, length) = field.get_offset_length();
+    (value >> offset) & ((1 << length) - 1) as u8
+}
+
 // 30x16, use as DisplayMemoryAddress High and Low
 #[inline]
 pub fn display_memory_address(row: u8, column: u8) -> u16 {
@@ -181,6 +204,48 @@ register_fields! {
     }
 }
 
+/// Character black level, percentage of OSD white level
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CharacterBlackLevel {
+    Percent0 = 0,
+    Percent10 = 1,
+    Percent20 = 2,
+    Percent30 = 3,
+}
+
+/// Character white level, percentage of OSD white level
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CharacterWhiteLevel {
+    Percent120 = 0,
+    Percent100 = 1,
+    Percent90 = 2,
+    Percent80 = 3,
+}
+
+register_fields! {
+    #[derive(Debug)]
+    pub enum RowBrightness {
+        CharacterBlackLevel = 2: 2,
+        CharacterWhiteLevel = 0: 2,
+    }
+}
+
+/// OSD black level control
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BlackLevel {
+    Auto,
+    /// Overwrites factory preset until power cycle, 0 ~ 15
+    Manual(u8),
+}
+
+register_fields! {
+    #[derive(Debug)]
+    pub enum OSDBlackLevel {
+        AutoBlackLevelDisable = 4: 1, // 0 enables automatic black level control
+        Level = 0: 4,                 // factory preset, volatile, manual level
+    }
+}
+
 pub enum CharacterMemoryStatus {
     Available = 0,
     Unavailable = 1,
@@ -219,14 +284,182 @@ pub enum Registers {
     CharacterMemoryAddressLow = 0xA, // 0~5bit
     CharacterMemoryDataIn = 0xB,
     OSDInsertionMux = 0xC,
-    Row0Bridghtness = 0x10,
+    Row0Brightness = 0x10,
+    Row1Brightness = 0x11,
+    Row2Brightness = 0x12,
+    Row3Brightness = 0x13,
+    Row4Brightness = 0x14,
+    Row5Brightness = 0x15,
+    Row6Brightness = 0x16,
+    Row7Brightness = 0x17,
+    Row8Brightness = 0x18,
+    Row9Brightness = 0x19,
+    Row10Brightness = 0x1A,
+    Row11Brightness = 0x1B,
+    Row12Brightness = 0x1C,
+    Row13Brightness = 0x1D,
+    Row14Brightness = 0x1E,
+    Row15Brightness = 0x1F,
+    OSDBlackLevel = 0x6C,
     Status = 0xA0,
     DisplayMemoryDataOut = 0xB0,   // read only
     CharacterMemoryDataOut = 0xC0, // read only
 }
 
+const ROW_BRIGHTNESS: [Registers; 16] = [
+    Registers::Row0Brightness,
+    Registers::Row1Brightness,
+    Registers::Row2Brightness,
+    Registers::Row3Brightness,
+    Registers::Row4Brightness,
+    Registers::Row5Brightness,
+    Registers::Row6Brightness,
+    Registers::Row7Brightness,
+    Registers::Row8Brightness,
+    Registers::Row9Brightness,
+    Registers::Row10Brightness,
+    Registers::Row11Brightness,
+    Registers::Row12Brightness,
+    Registers::Row13Brightness,
+    Registers::Row14Brightness,
+    Registers::Row15Brightness,
+];
+
 impl Registers {
+    #[deprecated(note = "use Row0Brightness")]
+    #[allow(non_upper_case_globals)]
+    pub const Row0Bridghtness: Registers = Registers::Row0Brightness;
+
     pub fn read_address(&self) -> u8 {
         *self as u8 | 0x80
     }
+
+    /// Brightness register of `row`, `None` if beyond 16 rows
+    pub fn row_brightness(row: usize) -> Option<Registers> {
+        ROW_BRIGHTNESS.get(row).copied()
+    }
+}
+
+// display memory register addresses, for raw SPI bytes
+pub(crate) const DMM: u8 = Registers::DisplayMemoryMode as u8;
+pub(crate) const DMAH: u8 = Registers::DisplayMemoryAddressHigh as u8;
+pub(crate) const DMAL: u8 = Registers::DisplayMemoryAddressLow as u8;
+pub(crate) const DMDI: u8 = Registers::DisplayMemoryDataIn as u8;
+pub(crate) const DMDO: u8 = Registers::DisplayMemoryDataOut as u8;
+
+/// Field enum of a register, tied to the register address
+pub trait RegisterFields: Field {
+    const REGISTER: Registers;
+}
+
+/// Field enum of a register that can also be written, excludes read-only ones
+pub trait WritableRegisterFields: RegisterFields {}
+
+macro_rules! register_address {
+    (read_only $($fields:ident => $register:ident,)*) => {
+        $(
+            impl RegisterFields for $fields {
+                const REGISTER: Registers = Registers::$register;
+            }
+        )*
+    };
+    ($($fields:ident => $register:ident,)*) => {
+        register_address!(read_only $($fields => $register,)*);
+        $(impl WritableRegisterFields for $fields {})*
+    };
+}
+
+register_address! {
+    VideoMode0 => VideoMode0,
+    VideoMode1 => VideoMode1,
+    HorizentalOffset => HorizentalOffset,
+    VerticalOffset => VerticalOffset,
+    DisplayMemoryMode => DisplayMemoryMode,
+    DisplayMemoryAddressHigh => DisplayMemoryAddressHigh,
+    DisplayMemoryAddressLow => DisplayMemoryAddressLow,
+    OSDInsertionMuxRegister => OSDInsertionMux,
+    OSDBlackLevel => OSDBlackLevel,
+}
+
+register_address! {
+    read_only
+    Status => Status,
+}
+
+/// Value not matching any variant
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct InvalidValue(pub u8);
+
+macro_rules! try_from_u8 {
+    ($($type:ident { $($variant:ident),* })*) => {
+        $(
+            impl TryFrom<u8> for $type {
+                type Error = InvalidValue;
+
+                fn try_from(value: u8) -> Result<Self, InvalidValue> {
+                    $(
+                        if value == Self::$variant as u8 {
+                            return Ok(Self::$variant);
+                        }
+                    )*
+                    Err(InvalidValue(value))
+                }
+            }
+        )*
+    };
+}
+
+try_from_u8! {
+    VerticalSync { Immediately, NextVSync }
+    Standard { NTSC, PAL }
+    OperationMode { Mode16Bit, Mode8Bit }
+    CharacterBlackLevel { Percent0, Percent10, Percent20, Percent30 }
+    CharacterWhiteLevel { Percent120, Percent100, Percent90, Percent80 }
+    CharacterMemoryStatus { Available, Unavailable }
+    CharacterMemoryMode { WriteToNVM, ReadFromNVM }
+}
+
+// 0b01 also selects auto sync
+impl TryFrom<u8> for SyncMode {
+    type Error = InvalidValue;
+
+    fn try_from(value: u8) -> Result<Self, InvalidValue> {
+        match value {
+            0b00 | 0b01 => Ok(Self::AutoSyncDetect),
+            0b10 => Ok(Self::External),
+            0b11 => Ok(Self::Internal),
+            _ => Err(InvalidValue(value)),
+        }
+    }
+}
+
+// 0b11 is also transparent
+impl TryFrom<u8> for Pixel {
+    type Error = InvalidValue;
+
+    fn try_from(value: u8) -> Result<Self, InvalidValue> {
+        match value {
+            0b00 => Ok(Self::Black),
+            0b01 | 0b11 => Ok(Self::Transparent),
+            0b10 => Ok(Self::White),
+            _ => Err(InvalidValue(value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::convert::TryFrom;
+
+    use super::{InvalidValue, Pixel, Standard, SyncMode};
+
+    #[test]
+    fn test_try_from() {
+        assert_eq!(Standard::try_from(1), Ok(Standard::PAL));
+        assert_eq!(SyncMode::try_from(0b11), Ok(SyncMode::Internal));
+        assert_eq!(SyncMode::try_from(0b01), Ok(SyncMode::AutoSyncDetect));
+        assert_eq!(SyncMode::try_from(4), Err(InvalidValue(4)));
+        assert!(matches!(Pixel::try_from(2), Ok(Pixel::White)));
+        assert!(matches!(Pixel::try_from(3), Ok(Pixel::Transparent)));
+    }
 }
@@ -0,0 +1,210 @@
+//! Render simulated screen into grayscale image, for snapshot tests
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::character_memory::{CHAR_HEIGHT, CHAR_WIDTH};
+use crate::registers::{field_value, Pixel, Registers, Standard, VideoMode0, VideoMode1};
+use crate::simulator::Simulator;
+use crate::COLUMN;
+
+pub const WHITE: u8 = 0xFF;
+pub const BLACK: u8 = 0;
+
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn write_pgm<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write!(writer, "P5\n{} {}\n255\n", self.width, self.height)?;
+        writer.write_all(&self.pixels)
+    }
+
+    /// 8bit grayscale PNG with stored (uncompressed) deflate blocks
+    pub fn write_png<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut raw = Vec::with_capacity((self.width + 1) * self.height);
+        for row in self.pixels.chunks(self.width) {
+            raw.push(0); // filter type none
+            raw.extend_from_slice(row);
+        }
+        let mut zlib = vec![0x78, 0x01];
+        let mut blocks = raw.chunks(0xFFFF).peekable();
+        while let Some(block) = blocks.next() {
+            zlib.push(blocks.peek().is_none() as u8);
+            zlib.extend_from_slice(&(block.len() as u16).to_le_bytes());
+            zlib.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+            zlib.extend_from_slice(block);
+        }
+        zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        header.extend_from_slice(&[8, 0, 0, 0, 0]); // 8bit grayscale
+
+        writer.write_all(b"\x89PNG\r\n\x1a\n")?;
+        write_png_chunk(&mut writer, b"IHDR", &header)?;
+        write_png_chunk(&mut writer, b"IDAT", &zlib)?;
+        write_png_chunk(&mut writer, b"IEND", &[])
+    }
+
+    /// Format chosen by extension, PNG unless `.pgm`
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let writer = BufWriter::new(File::create(path)?);
+        match path.extension() {
+            Some(extension) if extension == "pgm" => self.write_pgm(writer),
+            _ => self.write_png(writer),
+        }
+    }
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in bytes.iter() {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    b << 16 | a
+}
+
+fn crc32(chunks: &[&[u8]]) -> u32 {
+    let mut crc = 0xFFFFFFFFu32;
+    for &byte in chunks.iter().flat_map(|chunk| chunk.iter()) {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 > 0 { 0xEDB88320 ^ (crc >> 1) } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn write_png_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+    writer.write_all(&crc32(&[kind, data]).to_be_bytes())
+}
+
+/// Renders display memory with stored font, blinking chars are hidden
+/// when `blink_on` is false, transparent pixels show `video` level
+pub struct Renderer {
+    pub video: u8,
+    pub blink_on: bool,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self { video: 0x80, blink_on: true }
+    }
+}
+
+impl Renderer {
+    pub fn render(&self, simulator: &Simulator) -> Image {
+        let vm0 = simulator.register(Registers::VideoMode0);
+        let vm1 = simulator.register(Registers::VideoMode1);
+        let standard = match field_value(vm0, VideoMode0::Standard) {
+            0 => Standard::NTSC,
+            _ => Standard::PAL,
+        };
+        let rows = standard.rows();
+        let (width, height) = (COLUMN * CHAR_WIDTH, rows * CHAR_HEIGHT);
+        let mut image = Image { width, height, pixels: vec![self.video; width * height] };
+        if field_value(vm0, VideoMode0::EnableDisplay) == 0 {
+            return image;
+        }
+
+        // 0% ~ 49% of white, 7% each step
+        let gray = (field_value(vm1, VideoMode1::Brightness) as usize * 7 * 255 / 100) as u8;
+        let gray_background = field_value(vm1, VideoMode1::BackgroundMode) > 0;
+        for row in 0..rows as u8 {
+            for column in 0..COLUMN as u8 {
+                let attributes = simulator.attributes(row, column);
+                let background = match attributes.local_background_control || gray_background {
+                    true => gray,
+                    false => self.video,
+                };
+                let visible = self.blink_on || !attributes.blink;
+                let font = simulator.font(simulator.character(row, column));
+                for y in 0..CHAR_HEIGHT {
+                    for x in 0..CHAR_WIDTH {
+                        let byte = font[(y * CHAR_WIDTH + x) / 4];
+                        let pixel = (byte >> (6 - (x % 4) * 2)) & 0b11;
+                        let level = match (visible, pixel, attributes.revert) {
+                            (true, p, false) if p == Pixel::Black as u8 => BLACK,
+                            (true, p, true) if p == Pixel::Black as u8 => WHITE,
+                            (true, p, false) if p == Pixel::White as u8 => WHITE,
+                            (true, p, true) if p == Pixel::White as u8 => BLACK,
+                            _ => background,
+                        };
+                        let offset = (row as usize * CHAR_HEIGHT + y) * width;
+                        image.pixels[offset + column as usize * CHAR_WIDTH + x] = level;
+                    }
+                }
+            }
+        }
+        image
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Renderer, BLACK, WHITE};
+    use crate::simulator::Simulator;
+
+    fn simulator() -> Simulator {
+        let mut simulator = Simulator::new();
+        simulator.transaction(&hex!("00 48")); // PAL, display enabled
+        let font = simulator.font_mut(b'A');
+        font[0] = 0b00_10_01_11;
+        simulator
+    }
+
+    #[test]
+    fn test_render() {
+        let mut simulator = simulator();
+        simulator.transaction(&hex!("04 00 05 00 06 1F 07 41"));
+        let image = Renderer::default().render(&simulator);
+        assert_eq!((image.width, image.height), (360, 288));
+        let pixels: Vec<u8> = (0..4).map(|x| image.pixel(12 + x, 18)).collect();
+        assert_eq!(pixels, [BLACK, WHITE, 0x80, 0x80]);
+        assert_eq!(image.pixel(0, 0), 0x80);
+    }
+
+    #[test]
+    fn test_attributes() {
+        let mut simulator = simulator();
+        // invert + local background, then blink
+        simulator.transaction(&hex!("04 28 05 00 06 00 07 41 04 10 06 01 07 41"));
+        let image = Renderer::default().render(&simulator);
+        let pixels: Vec<u8> = (0..4).map(|x| image.pixel(x, 0)).collect();
+        assert_eq!(pixels, [WHITE, BLACK, 71, 71]);
+        assert_eq!(image.pixel(12, 0), BLACK);
+
+        let image = Renderer { blink_on: false, ..Default::default() }.render(&simulator);
+        assert_eq!(image.pixel(12, 0), 0x80);
+    }
+
+    #[test]
+    fn test_image_format() {
+        let image = Renderer::default().render(&Simulator::new());
+        assert_eq!(image.height, 13 * 18);
+        let mut pgm = Vec::new();
+        image.write_pgm(&mut pgm).unwrap();
+        assert!(pgm.starts_with(b"P5\n360 234\n255\n"));
+        assert_eq!(pgm.len(), 15 + 360 * 234);
+
+        let mut png = Vec::new();
+        image.write_png(&mut png).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0DIHDR"));
+        assert!(png.ends_with(&hex!("00 00 00 00 49 45 4E 44 AE 42 60 82")));
+    }
+}
@@ -0,0 +1,196 @@
+use core::cmp::min;
+
+use crate::incremental_writer::IncrementalWriter;
+use crate::lines_writer::LinesWriter;
+use crate::sparse_writer::SparseWriter;
+use crate::Display;
+
+/// Smallest buffer every writer accepts
+pub const MIN_CHUNK_SIZE: usize = 10;
+
+/// Writer producing `Display` chunks, empty one when nothing left
+pub trait Writer {
+    fn write_chunk<'b>(&mut self, buffer: &'b mut [u8]) -> Display<'b>;
+}
+
+impl<'a, T: AsRef<[u8]>> Writer for LinesWriter<'a, T> {
+    fn write_chunk<'b>(&mut self, buffer: &'b mut [u8]) -> Display<'b> {
+        self.write(buffer)
+    }
+}
+
+impl<'a> Writer for IncrementalWriter<'a> {
+    fn write_chunk<'b>(&mut self, buffer: &'b mut [u8]) -> Display<'b> {
+        self.write(buffer).unwrap_or(Display(&[]))
+    }
+}
+
+impl<'a> Writer for SparseWriter<'a> {
+    fn write_chunk<'b>(&mut self, buffer: &'b mut [u8]) -> Display<'b> {
+        self.write(buffer)
+    }
+}
+
+/// Pending update of an OSD element
+pub struct Update<'a> {
+    writer: &'a mut dyn Writer,
+    priority: u8,
+    deadline: u16,
+    waited: u16,
+}
+
+impl<'a> Update<'a> {
+    fn is_overdue(&self) -> bool {
+        self.waited >= self.deadline
+    }
+
+    // priority aged by waited ticks, so that low priority updates won't starve
+    fn effective_priority(&self) -> u32 {
+        self.priority as u32 + self.waited as u32
+    }
+}
+
+/// Emits at most `budget` bytes of writer output per tick, overdue updates first,
+/// then higher priority, where priority increases every tick an update waits
+pub struct Scheduler<'a, 'w> {
+    updates: &'a mut [Option<Update<'w>>],
+    budget: usize,
+}
+
+impl<'a, 'w> Scheduler<'a, 'w> {
+    pub fn new(slots: &'a mut [Option<Update<'w>>], budget: usize) -> Self {
+        assert!(budget >= MIN_CHUNK_SIZE);
+        Self { updates: slots, budget }
+    }
+
+    pub fn pending(&self) -> usize {
+        self.updates.iter().filter(|u| u.is_some()).count()
+    }
+
+    /// `deadline` in ticks, returns false if no free slot
+    pub fn submit(&mut self, writer: &'w mut dyn Writer, priority: u8, deadline: u16) -> bool {
+        match self.updates.iter_mut().find(|u| u.is_none()) {
+            Some(slot) => {
+                *slot = Some(Update { writer, priority, deadline, waited: 0 });
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn select(&self) -> Option<usize> {
+        let updates = self.updates.iter().enumerate();
+        let updates = updates.filter_map(|(i, u)| u.as_ref().map(|u| (i, u)));
+        let key = |u: &Update| match u.is_overdue() {
+            true => (1, u.waited as u32 - u.deadline as u32),
+            false => (0, u.effective_priority()),
+        };
+        updates.max_by_key(|(_, u)| key(u)).map(|(i, _)| i)
+    }
+
+    /// Write chunks with `write` until budget used up, returns bytes written,
+    /// `buffer` must hold at least `MIN_CHUNK_SIZE` bytes
+    pub fn tick<E, F>(&mut self, buffer: &mut [u8], mut write: F) -> Result<usize, E>
+    where
+        F: FnMut(&Display) -> Result<(), E>,
+    {
+        assert!(buffer.len() >= MIN_CHUNK_SIZE);
+        let mut remain = self.budget;
+        while remain >= MIN_CHUNK_SIZE {
+            let index = match self.select() {
+                Some(index) => index,
+                None => break,
+            };
+            let update = self.updates[index].as_mut().unwrap();
+            let size = min(buffer.len(), remain);
+            let display = update.writer.write_chunk(&mut buffer[..size]);
+            if display.0.is_empty() {
+                self.updates[index] = None;
+                continue;
+            }
+            remain -= display.0.len();
+            write(&display)?;
+        }
+        for update in self.updates.iter_mut().flatten() {
+            update.waited = update.waited.saturating_add(1);
+        }
+        Ok(self.budget - remain)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Scheduler;
+    use crate::incremental_writer::IncrementalWriter;
+    use crate::simulator::Simulator;
+
+    fn tick(scheduler: &mut Scheduler, simulator: &mut Simulator) -> usize {
+        let mut buffer = [0u8; 64];
+        let result: Result<usize, ()> = scheduler.tick(&mut buffer, |display| {
+            simulator.transaction(display.0);
+            Ok(())
+        });
+        result.unwrap()
+    }
+
+    #[test]
+    fn test_budget() {
+        let mut simulator = Simulator::new();
+        let mut slots = [None, None];
+        let mut scheduler = Scheduler::new(&mut slots, 16);
+        let mut writer = IncrementalWriter::new(b"abcdefgh", 0, 0, Default::default());
+        assert!(scheduler.submit(&mut writer, 0, 10));
+        assert_eq!(tick(&mut scheduler, &mut simulator), 16);
+        assert_eq!(simulator.line(0)[..5], *b"abcd\0");
+        assert_eq!(tick(&mut scheduler, &mut simulator), 16);
+        assert_eq!(simulator.line(0)[..8], *b"abcdefgh");
+        assert_eq!(scheduler.pending(), 1);
+        assert_eq!(tick(&mut scheduler, &mut simulator), 0);
+        assert_eq!(scheduler.pending(), 0);
+    }
+
+    #[test]
+    fn test_priority_and_deadline() {
+        let mut simulator = Simulator::new();
+        let mut slots = [None, None, None];
+        let mut scheduler = Scheduler::new(&mut slots, 12);
+        let mut low = IncrementalWriter::new(b"l", 0, 0, Default::default());
+        let mut high = IncrementalWriter::new(b"h", 1, 0, Default::default());
+        let mut urgent = IncrementalWriter::new(b"u", 2, 0, Default::default());
+        let mut extra = IncrementalWriter::new(b"x", 3, 0, Default::default());
+        assert!(scheduler.submit(&mut low, 0, 10));
+        assert!(scheduler.submit(&mut high, 5, 10));
+        assert!(scheduler.submit(&mut urgent, 0, 0));
+        assert!(!scheduler.submit(&mut extra, 9, 0));
+        tick(&mut scheduler, &mut simulator);
+        assert_eq!(simulator.character(2, 0), b'u');
+        tick(&mut scheduler, &mut simulator);
+        assert_eq!(simulator.character(1, 0), b'h');
+        assert_eq!(simulator.character(0, 0), 0);
+        tick(&mut scheduler, &mut simulator);
+        assert_eq!(simulator.character(0, 0), b'l');
+    }
+
+    #[test]
+    fn test_no_starvation() {
+        let mut simulator = Simulator::new();
+        let mut low = IncrementalWriter::new(b"l", 0, 0, Default::default());
+        let mut highs: Vec<IncrementalWriter> =
+            (0..8).map(|_| IncrementalWriter::new(b"h", 1, 0, Default::default())).collect();
+        // finished update frees its slot only on next tick
+        let mut slots = [None, None, None];
+        let mut scheduler = Scheduler::new(&mut slots, 12);
+        assert!(scheduler.submit(&mut low, 0, u16::MAX));
+        let mut ticks = 0;
+        for high in highs.iter_mut() {
+            assert!(scheduler.submit(high, 3, u16::MAX));
+            tick(&mut scheduler, &mut simulator);
+            ticks += 1;
+            if simulator.character(0, 0) == b'l' {
+                break;
+            }
+        }
+        assert_eq!(simulator.character(0, 0), b'l');
+        assert!(ticks <= 5);
+    }
+}
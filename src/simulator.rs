@@ -0,0 +1,325 @@
+//! Software model of MAX7456 consuming SPI byte streams, for host side tests
+
+use crate::character_memory::{CharData, CHAR_DATA_SIZE};
+use crate::registers::{
+    field_value, CharacterAttribute, CharacterMemoryMode, DisplayMemoryAddressHigh,
+    DisplayMemoryMode, Registers, VideoMode0, DMAH, DMAL, DMDI, DMDO, DMM,
+};
+use crate::{display_memory_address, Attributes, COLUMN, ROW};
+
+pub const DISPLAY_MEMORY_SIZE: usize = ROW * COLUMN;
+
+const VM0: u8 = Registers::VideoMode0 as u8;
+const CMM: u8 = Registers::CharacterMemoryMode as u8;
+const CMAH: u8 = Registers::CharacterMemoryAddressHigh as u8;
+const CMAL: u8 = Registers::CharacterMemoryAddressLow as u8;
+const CMDI: u8 = Registers::CharacterMemoryDataIn as u8;
+const CMDO: u8 = Registers::CharacterMemoryDataOut as u8;
+const STAT: u8 = Registers::Status as u8;
+
+fn is_set<F: peripheral_register::Field>(value: u8, field: F) -> bool {
+    field_value(value, field) > 0
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum State {
+    Address,
+    Write(u8),
+    Read(u8),
+}
+
+pub struct Simulator {
+    registers: [u8; 0x80],
+    status: u8,
+    characters: [u8; DISPLAY_MEMORY_SIZE],
+    attributes: [u8; DISPLAY_MEMORY_SIZE],
+    fonts: Vec<CharData>,
+    shadow: CharData,
+    selected: bool,
+    state: State,
+}
+
+impl Default for Simulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Simulator {
+    pub fn new() -> Self {
+        let mut simulator = Self {
+            registers: [0u8; 0x80],
+            status: 0,
+            characters: [0u8; DISPLAY_MEMORY_SIZE],
+            attributes: [0u8; DISPLAY_MEMORY_SIZE],
+            fonts: vec![[0xFFu8; CHAR_DATA_SIZE]; 256], // erased NVM, all transparent
+            shadow: [0u8; CHAR_DATA_SIZE],
+            selected: false,
+            state: State::Address,
+        };
+        simulator.reset();
+        simulator
+    }
+
+    /// Power-on defaults, display memory cleared while font memory retained
+    pub fn reset(&mut self) {
+        self.registers = [0u8; 0x80];
+        self.registers[Registers::VideoMode1 as usize] = 0x47;
+        self.registers[Registers::HorizentalOffset as usize] = 0x20;
+        self.registers[Registers::VerticalOffset as usize] = 0x10;
+        self.registers[Registers::OSDInsertionMux as usize] = 0x1B;
+        for row in 0..ROW {
+            self.registers[Registers::row_brightness(row).unwrap() as usize] = 0x01;
+        }
+        self.characters = [0u8; DISPLAY_MEMORY_SIZE];
+        self.attributes = [0u8; DISPLAY_MEMORY_SIZE];
+        self.state = State::Address;
+    }
+
+    /// Chip select, `true` for CS low
+    pub fn select(&mut self, selected: bool) {
+        self.selected = selected;
+        self.state = State::Address;
+    }
+
+    /// Exchange one byte, returns MISO byte
+    pub fn exchange(&mut self, mosi: u8) -> u8 {
+        if !self.selected {
+            return 0;
+        }
+        match self.state {
+            State::Address if mosi & 0x80 > 0 => self.state = State::Read(mosi),
+            State::Address => self.state = State::Write(mosi),
+            State::Write(address) => {
+                self.write_register(address, mosi);
+                self.state = State::Address;
+            }
+            State::Read(address) => {
+                self.state = State::Address;
+                return self.read_register(address);
+            }
+        }
+        0
+    }
+
+    /// Whether next byte exchanged is a read slot
+    pub fn is_reading(&self) -> bool {
+        self.selected && matches!(self.state, State::Read(_))
+    }
+
+    /// Feed a whole CS framed transaction
+    pub fn transaction(&mut self, bytes: &[u8]) {
+        self.select(true);
+        for &byte in bytes.iter() {
+            self.exchange(byte);
+        }
+        self.select(false);
+    }
+
+    pub fn register(&self, reg: Registers) -> u8 {
+        self.read_register(reg.read_address())
+    }
+
+    pub fn set_status(&mut self, status: u8) {
+        self.status = status;
+    }
+
+    pub fn character(&self, row: u8, column: u8) -> u8 {
+        self.characters[display_memory_address(row, column) as usize]
+    }
+
+    pub fn attributes(&self, row: u8, column: u8) -> Attributes {
+        let attribute = self.attributes[display_memory_address(row, column) as usize];
+        Attributes {
+            local_background_control: is_set(attribute, CharacterAttribute::LocalBackgroundControl),
+            blink: is_set(attribute, CharacterAttribute::Blink),
+            revert: is_set(attribute, CharacterAttribute::Invert),
+        }
+    }
+
+    /// Character address bytes of display memory, indexed by `display_memory_address`
+    pub fn display_memory(&self) -> &[u8] {
+        &self.characters
+    }
+
+    /// Display memory of given row, useful for comparing with text
+    pub fn line(&self, row: u8) -> &[u8] {
+        let address = display_memory_address(row, 0) as usize;
+        &self.characters[address..address + COLUMN]
+    }
+
+    pub fn font(&self, index: u8) -> &CharData {
+        &self.fonts[index as usize]
+    }
+
+    pub fn font_mut(&mut self, index: u8) -> &mut CharData {
+        &mut self.fonts[index as usize]
+    }
+
+    fn display_memory_address(&self) -> usize {
+        let high = self.registers[DMAH as usize];
+        let high = is_set(high, DisplayMemoryAddressHigh::Address8) as usize;
+        high << 8 | self.registers[DMAL as usize] as usize
+    }
+
+    fn set_display_memory_address(&mut self, address: usize) {
+        let high = &mut self.registers[DMAH as usize];
+        *high = (*high & !1) | (address >> 8) as u8 & 1;
+        self.registers[DMAL as usize] = address as u8;
+    }
+
+    fn write_display_memory(&mut self, value: u8) {
+        let dmm = self.registers[DMM as usize];
+        let auto_increment = is_set(dmm, DisplayMemoryMode::AutoIncrement);
+        if auto_increment && value == 0xFF {
+            self.registers[DMM as usize] &= !1;
+            return;
+        }
+        let address = self.display_memory_address();
+        if address < DISPLAY_MEMORY_SIZE {
+            if !is_set(dmm, DisplayMemoryMode::OperationMode) {
+                self.characters[address] = value;
+                self.attributes[address] = (dmm >> 3) & 0b111;
+            } else if is_set(self.registers[DMAH as usize], DisplayMemoryAddressHigh::ByteSelection)
+            {
+                self.attributes[address] = value & 0b111;
+            } else {
+                self.characters[address] = value;
+            }
+        }
+        if auto_increment {
+            self.set_display_memory_address(address + 1);
+        }
+    }
+
+    fn write_register(&mut self, address: u8, value: u8) {
+        match address {
+            VM0 if is_set(value, VideoMode0::SoftwareReset) => self.reset(),
+            DMM if is_set(value, DisplayMemoryMode::Clear) => {
+                self.characters = [0u8; DISPLAY_MEMORY_SIZE];
+                self.attributes = [0u8; DISPLAY_MEMORY_SIZE];
+                self.registers[DMM as usize] = value & !0b101;
+            }
+            DMDI => self.write_display_memory(value),
+            CMM if value == CharacterMemoryMode::WriteToNVM as u8 => {
+                self.fonts[self.registers[CMAH as usize] as usize] = self.shadow;
+            }
+            CMM if value == CharacterMemoryMode::ReadFromNVM as u8 => {
+                self.shadow = self.fonts[self.registers[CMAH as usize] as usize];
+            }
+            CMAL => self.registers[CMAL as usize] = value & 0x3F,
+            CMDI => {
+                let index = self.registers[CMAL as usize] as usize % CHAR_DATA_SIZE;
+                self.shadow[index] = value;
+            }
+            _ => self.registers[address as usize] = value,
+        }
+    }
+
+    fn read_register(&self, address: u8) -> u8 {
+        match address {
+            STAT => self.status,
+            DMDO => {
+                let address = self.display_memory_address();
+                if address >= DISPLAY_MEMORY_SIZE {
+                    return 0;
+                }
+                let dmah = self.registers[DMAH as usize];
+                if is_set(dmah, DisplayMemoryAddressHigh::ByteSelection) {
+                    self.attributes[address]
+                } else {
+                    self.characters[address]
+                }
+            }
+            CMDO => self.shadow[self.registers[CMAL as usize] as usize % CHAR_DATA_SIZE],
+            _ => self.registers[(address & 0x7F) as usize],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Simulator;
+    use crate::character_memory::{build_store_char_operation, STORE_CHAR_BUFFER_SIZE};
+    use crate::incremental_writer::IncrementalWriter;
+    use crate::lines_writer::LinesWriter;
+    use crate::registers::Registers;
+    use crate::Attributes;
+
+    #[test]
+    fn test_lines_writer() {
+        let mut simulator = Simulator::new();
+        let mut lines = [[0u8; 30]; 16];
+        lines[7][29] = b't';
+        lines[8][29] = b't';
+        let mut writer = LinesWriter::new(&lines, Default::default());
+        let mut output = [0u8; 32];
+        simulator.transaction(writer.write(&mut output).0);
+        assert_eq!(simulator.character(7, 29), b't');
+        assert_eq!(simulator.character(8, 29), b't');
+        assert_eq!(simulator.display_memory().iter().filter(|&&b| b > 0).count(), 2);
+    }
+
+    #[test]
+    fn test_incremental_writer() {
+        let mut simulator = Simulator::new();
+        let attributes = Attributes { blink: true, ..Default::default() };
+        let mut writer = IncrementalWriter::new(b"test", 1, 28, attributes);
+        let mut output = [0u8; 32];
+        simulator.transaction(writer.write(&mut output).unwrap().0);
+        assert_eq!(&simulator.line(1)[28..], b"te");
+        assert_eq!(&simulator.line(2)[..2], b"st");
+        assert_eq!(simulator.attributes(2, 0), attributes);
+        assert_eq!(simulator.register(Registers::DisplayMemoryMode) & 1, 0);
+    }
+
+    #[test]
+    fn test_8bit_mode_and_clear() {
+        let mut simulator = Simulator::new();
+        simulator.transaction(&hex!("04 40 05 00 06 01 07 41 05 02 07 02"));
+        assert_eq!(simulator.character(0, 1), b'A');
+        assert!(simulator.attributes(0, 1).blink);
+        simulator.transaction(&[0x05, 0x02, 0x06, 0x01]);
+        simulator.select(true);
+        simulator.exchange(Registers::DisplayMemoryDataOut as u8);
+        assert_eq!(simulator.exchange(0), 0x02);
+        simulator.select(false);
+
+        simulator.transaction(&hex!("04 04"));
+        assert_eq!(simulator.character(0, 1), 0);
+        assert_eq!(simulator.register(Registers::DisplayMemoryMode), 0);
+    }
+
+    #[test]
+    fn test_ignore_unselected() {
+        let mut simulator = Simulator::new();
+        simulator.exchange(Registers::VideoMode1 as u8);
+        simulator.exchange(0);
+        assert_eq!(simulator.register(Registers::VideoMode1), 0x47);
+    }
+
+    #[test]
+    fn test_character_memory() {
+        let mut simulator = Simulator::new();
+        let data = [0x55u8; 64];
+        let mut transaction = [0u8; STORE_CHAR_BUFFER_SIZE];
+        build_store_char_operation(&data, 10, &mut transaction);
+        simulator.transaction(&transaction);
+        assert_eq!(simulator.font(10), &data);
+
+        simulator.transaction(&hex!("09 0A 08 50 0A 3F"));
+        simulator.select(true);
+        simulator.exchange(Registers::CharacterMemoryDataOut as u8);
+        assert_eq!(simulator.exchange(0), 0x55);
+        simulator.select(false);
+    }
+
+    #[test]
+    fn test_software_reset() {
+        let mut simulator = Simulator::new();
+        simulator.transaction(&hex!("02 00 05 00 06 00 07 41"));
+        simulator.transaction(&hex!("00 02"));
+        assert_eq!(simulator.register(Registers::HorizentalOffset), 0x20);
+        assert_eq!(simulator.character(0, 0), 0);
+    }
+}
@@ -0,0 +1,211 @@
+use core::cmp::min;
+
+use crate::planner::{plan, Estimate, Plan};
+use crate::registers::Registers;
+use crate::{display_memory_address, Attributes, Display, COLUMN, ROW};
+
+/// Storage of a cell to be updated by `SparseWriter`
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Cell {
+    pub(crate) address: u16,
+    pub(crate) byte: u8,
+    pub(crate) attributes: Attributes,
+    pub(crate) plan: Plan,
+}
+
+/// Writer of scattered cells, sorted by address, planned to take least bytes
+/// by choosing between addressed write and auto increment run
+pub struct SparseWriter<'a> {
+    cells: &'a [Cell],
+    index: usize,
+    estimate: Estimate,
+    high_known: bool,
+    run_remain: usize,
+}
+
+impl<'a> SparseWriter<'a> {
+    /// Cells out of screen or beyond capacity of `storage` are dropped,
+    /// later cell overrides earlier one of same position
+    pub fn new<I>(cells: I, storage: &'a mut [Cell]) -> Self
+    where
+        I: IntoIterator<Item = (u8, u8, u8, Attributes)>,
+    {
+        let mut length = 0;
+        for (row, column, byte, attributes) in cells.into_iter() {
+            if row as usize >= ROW || column as usize >= COLUMN {
+                continue;
+            }
+            let address = display_memory_address(row, column);
+            let cell = Cell { address, byte, attributes, plan: Plan::default() };
+            match storage[..length].iter().position(|c| c.address == cell.address) {
+                Some(index) => storage[index] = cell,
+                None if length < storage.len() => {
+                    storage[length] = cell;
+                    length += 1;
+                }
+                None => continue,
+            }
+        }
+        let cells = &mut storage[..length];
+        cells.sort_unstable_by_key(|cell| cell.address);
+        let estimate = plan(cells);
+        Self { cells, index: 0, estimate, high_known: false, run_remain: 0 }
+    }
+
+    /// Full screen write of lines, cells holding `skip` are left untouched,
+    /// `storage` of `ROW * COLUMN` cells is enough for any lines
+    pub fn from_lines<T: AsRef<[u8]>>(
+        lines: &[T],
+        attributes: Attributes,
+        skip: Option<u8>,
+        storage: &'a mut [Cell],
+    ) -> Self {
+        let cells = lines.iter().enumerate().flat_map(|(row, line)| {
+            let line = line.as_ref();
+            let line = &line[..min(line.len(), COLUMN)];
+            let cells = line.iter().enumerate().filter(move |(_, &byte)| Some(byte) != skip);
+            cells.map(move |(column, &byte)| (row as u8, column as u8, byte, attributes))
+        });
+        Self::new(cells.take(ROW * COLUMN), storage)
+    }
+
+    /// Bytes required if written in a single buffer
+    pub fn estimate(&self) -> Estimate {
+        self.estimate
+    }
+
+    pub fn remain(&self) -> usize {
+        self.cells.len() - self.index
+    }
+
+    pub fn write<'b>(&mut self, buffer: &'b mut [u8]) -> Display<'b> {
+        assert!(buffer.len() >= 10);
+
+        let length = buffer.len();
+        let mut offset = 0;
+        let mut dmm: Option<u8> = None;
+        let mut high: Option<u8> = None;
+        let mut emit = |offset: &mut usize, reg: Registers, value: u8| {
+            buffer[*offset] = reg as u8;
+            buffer[*offset + 1] = value;
+            *offset += 2;
+        };
+        self.high_known = false;
+        while self.index < self.cells.len() {
+            let cell = self.cells[self.index];
+            let cell_dmm = cell.attributes.display_memory_mode(false);
+            let cell_high = (cell.address >> 8) as u8;
+            if self.run_remain == 0 {
+                self.run_remain = cell.plan.run(self.high_known);
+            }
+
+            if self.run_remain > 0 {
+                if offset + 10 > length {
+                    break;
+                }
+                let run_dmm = cell.attributes.display_memory_mode(true);
+                emit(&mut offset, Registers::DisplayMemoryMode, run_dmm);
+                emit(&mut offset, Registers::DisplayMemoryAddressHigh, cell_high);
+                emit(&mut offset, Registers::DisplayMemoryAddressLow, cell.address as u8);
+                let end = self.index + self.run_remain;
+                for cell in self.cells[self.index..end].iter() {
+                    if offset + 4 > length {
+                        break;
+                    }
+                    emit(&mut offset, Registers::DisplayMemoryDataIn, cell.byte);
+                    self.index += 1;
+                    self.run_remain -= 1;
+                }
+                emit(&mut offset, Registers::DisplayMemoryDataIn, 0xFF);
+                dmm = Some(cell_dmm); // auto increment bit cleared by 0xFF
+                high = None;
+                self.high_known = false;
+                continue;
+            }
+
+            let dmm_cost = if dmm != Some(cell_dmm) { 2 } else { 0 };
+            let high_cost = if high != Some(cell_high) { 2 } else { 0 };
+            if offset + dmm_cost + high_cost + 4 > length {
+                break;
+            }
+            if dmm_cost > 0 {
+                emit(&mut offset, Registers::DisplayMemoryMode, cell_dmm);
+                dmm = Some(cell_dmm);
+            }
+            if high_cost > 0 {
+                emit(&mut offset, Registers::DisplayMemoryAddressHigh, cell_high);
+                high = Some(cell_high);
+            }
+            emit(&mut offset, Registers::DisplayMemoryAddressLow, cell.address as u8);
+            emit(&mut offset, Registers::DisplayMemoryDataIn, cell.byte);
+            self.index += 1;
+            self.high_known = true;
+        }
+        Display(&buffer[..offset])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Cell, SparseWriter};
+    use crate::simulator::Simulator;
+    use crate::Attributes;
+
+    #[test]
+    fn test_scattered() {
+        let mut storage = [Cell::default(); 8];
+        let none = Attributes::default();
+        let cells = [(8, 29, b't', none), (7, 29, b't', none), (8, 15, b'x', none)];
+        let mut writer = SparseWriter::new(cells.iter().copied(), &mut storage);
+        let mut output = [0u8; 32];
+        let expected = hex!("04 00 05 00 06 EF 07 74 06 FF 07 78 05 01 06 0D 07 74");
+        assert_eq!(writer.write(&mut output).0, expected);
+        assert_eq!(writer.write(&mut output).0.len(), 0);
+    }
+
+    #[test]
+    fn test_run_and_attributes() {
+        let mut storage = [Cell::default(); 8];
+        let none = Attributes::default();
+        let blink = Attributes { blink: true, ..Default::default() };
+        let cells = (0..5).map(|i| (0, i, b'a' + i, none)).chain([(0, 9, b'!', blink)]);
+        let mut writer = SparseWriter::new(cells, &mut storage);
+        let mut output = [0u8; 32];
+        let expected = hex!(
+            "04 01 05 00 06 00 07 61 07 62 07 63 07 64 07 65 07 FF
+             04 10 05 00 06 09 07 21"
+        );
+        assert_eq!(writer.write(&mut output).0, expected);
+    }
+
+    #[test]
+    fn test_override_and_capacity() {
+        let mut storage = [Cell::default(); 2];
+        let none = Attributes::default();
+        let cells = [(0, 0, b'a', none), (0, 0, b'b', none), (16, 0, b'c', none)];
+        let writer = SparseWriter::new(cells.iter().copied(), &mut storage);
+        assert_eq!(writer.remain(), 1);
+        let cells = [(0, 0, b'a', none), (0, 1, b'b', none), (0, 2, b'c', none)];
+        let writer = SparseWriter::new(cells.iter().copied(), &mut storage);
+        assert_eq!(writer.remain(), 2);
+    }
+
+    #[test]
+    fn test_chunks() {
+        let mut simulator = Simulator::new();
+        let mut storage = [Cell::default(); 32];
+        let none = Attributes::default();
+        let cells = (0..10).map(|i| (1, i, b'0' + i, none)).chain([(2, 0, 0xFF, none)]);
+        let mut writer = SparseWriter::new(cells, &mut storage);
+        let mut output = [0u8; 12];
+        loop {
+            let display = writer.write(&mut output);
+            if display.0.is_empty() {
+                break;
+            }
+            simulator.transaction(display.0);
+        }
+        assert_eq!(simulator.line(1)[..10], *b"0123456789");
+        assert_eq!(simulator.character(2, 0), 0xFF);
+    }
+}
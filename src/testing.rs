@@ -0,0 +1,214 @@
+//! Recording SPI and chip select mocks for testing code driving `MAX7456`
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use embedded_hal::blocking::delay::{DelayMs, DelayUs};
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::simulator::Simulator;
+
+#[derive(Default)]
+struct Bus {
+    selected: bool,
+    transactions: Vec<Vec<u8>>,
+    unframed: Vec<u8>,
+    responses: VecDeque<u8>,
+    simulator: Option<Simulator>,
+}
+
+impl Bus {
+    fn exchange(&mut self, mosi: u8, read: bool) -> u8 {
+        if !self.selected {
+            self.unframed.push(mosi);
+            return 0;
+        }
+        self.transactions.last_mut().unwrap().push(mosi);
+        let miso = self.simulator.as_mut().map(|s| s.exchange(mosi)).unwrap_or_default();
+        match read {
+            true => self.responses.pop_front().unwrap_or(miso),
+            false => miso,
+        }
+    }
+}
+
+/// Shared state of a `RecordingSpi` and `RecordingPin` pair,
+/// each CS low to high period is recorded as one transaction
+#[derive(Clone, Default)]
+pub struct Recorder(Rc<RefCell<Bus>>);
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// MISO bytes come from simulator unless scripted with `respond`
+    pub fn with_simulator(simulator: Simulator) -> Self {
+        let recorder = Self::default();
+        recorder.0.borrow_mut().simulator = Some(simulator);
+        recorder
+    }
+
+    pub fn spi(&self) -> RecordingSpi {
+        RecordingSpi(self.clone())
+    }
+
+    pub fn cs(&self) -> RecordingPin {
+        RecordingPin(self.clone())
+    }
+
+    /// Script MISO bytes for following `transfer`s, e.g. status busy then ready
+    pub fn respond(&self, bytes: &[u8]) {
+        self.0.borrow_mut().responses.extend(bytes.iter());
+    }
+
+    pub fn transactions(&self) -> Vec<Vec<u8>> {
+        self.0.borrow().transactions.clone()
+    }
+
+    pub fn take_transactions(&self) -> Vec<Vec<u8>> {
+        std::mem::take(&mut self.0.borrow_mut().transactions)
+    }
+
+    /// Bytes written while CS is high, which chip won't receive
+    pub fn unframed(&self) -> Vec<u8> {
+        self.0.borrow().unframed.clone()
+    }
+
+    pub fn simulator<R>(&self, f: impl FnOnce(&mut Simulator) -> R) -> Option<R> {
+        self.0.borrow_mut().simulator.as_mut().map(f)
+    }
+
+    /// Asserts and consumes recorded transactions
+    pub fn expect(&self, expected: &[&[u8]]) {
+        assert_eq!(self.unframed(), [], "bytes written without chip select");
+        let transactions = self.take_transactions();
+        let transactions: Vec<&[u8]> = transactions.iter().map(|t| &t[..]).collect();
+        assert_eq!(transactions, expected);
+    }
+}
+
+pub struct RecordingSpi(Recorder);
+
+impl Write<u8> for RecordingSpi {
+    type Error = Infallible;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Infallible> {
+        let mut bus = (self.0).0.borrow_mut();
+        for &word in words.iter() {
+            bus.exchange(word, false);
+        }
+        Ok(())
+    }
+}
+
+impl Transfer<u8> for RecordingSpi {
+    type Error = Infallible;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Infallible> {
+        let mut bus = (self.0).0.borrow_mut();
+        for word in words.iter_mut() {
+            *word = bus.exchange(*word, true);
+        }
+        Ok(words)
+    }
+}
+
+pub struct RecordingPin(Recorder);
+
+impl OutputPin for RecordingPin {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        let mut bus = (self.0).0.borrow_mut();
+        if !bus.selected {
+            bus.transactions.push(Vec::new());
+        }
+        bus.selected = true;
+        if let Some(simulator) = bus.simulator.as_mut() {
+            simulator.select(true);
+        }
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        let mut bus = (self.0).0.borrow_mut();
+        bus.selected = false;
+        if let Some(simulator) = bus.simulator.as_mut() {
+            simulator.select(false);
+        }
+        Ok(())
+    }
+}
+
+/// Delay which only accumulates requested time
+#[derive(Default)]
+pub struct MockDelay {
+    pub elapsed_us: u64,
+}
+
+impl DelayMs<u8> for MockDelay {
+    fn delay_ms(&mut self, ms: u8) {
+        self.elapsed_us += ms as u64 * 1000;
+    }
+}
+
+impl DelayUs<u8> for MockDelay {
+    fn delay_us(&mut self, us: u8) {
+        self.elapsed_us += us as u64;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MockDelay, Recorder};
+    use crate::character_memory::{build_store_char_operation, STORE_CHAR_BUFFER_SIZE};
+    use crate::simulator::Simulator;
+    use crate::MAX7456;
+
+    #[test]
+    fn test_reset() {
+        let recorder = Recorder::new();
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        recorder.respond(&[0x02, 0x00]); // in reset, reset done
+        let mut delay = MockDelay::default();
+        assert!(max7456.reset(&mut delay).unwrap());
+        recorder.expect(&[&[0x00, 0x02], &[0x80, 0x00], &[0x80, 0x00]]);
+        assert_eq!(delay.elapsed_us, 51_000);
+    }
+
+    #[test]
+    fn test_store_char() {
+        let recorder = Recorder::new();
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        let data = [0x55u8; 64];
+        recorder.respond(&[0x20, 0x20, 0x00]); // busy, busy, available
+        assert!(max7456.store_char(10, &data, &mut MockDelay::default()).unwrap());
+        let mut transaction = [0u8; STORE_CHAR_BUFFER_SIZE];
+        build_store_char_operation(&data, 10, &mut transaction);
+        recorder.expect(&[&transaction, &[0xA0, 0x00], &[0xA0, 0x00], &[0xA0, 0x00]]);
+
+        max7456.enable_display(true).unwrap();
+        assert!(!max7456.store_char(10, &data, &mut MockDelay::default()).unwrap());
+        recorder.expect(&[&[0x80, 0x00], &[0x00, 0x08]]);
+    }
+
+    #[test]
+    fn test_load_char() {
+        let mut simulator = Simulator::new();
+        simulator.font_mut(0x41)[1] = 0xAA;
+        let recorder = Recorder::with_simulator(simulator);
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        let mut data = [0u8; 64];
+        assert!(max7456.load_char(0x41, &mut data).unwrap());
+        assert_eq!(data[..3], [0xFF, 0xAA, 0xFF]);
+        let transactions = recorder.take_transactions();
+        assert_eq!(transactions.len(), 1 + 64 * 2);
+        assert_eq!(transactions[0], [0x09, 0x41, 0x08, 0x50]);
+        assert_eq!(transactions[3], [0x0A, 0x01]);
+        assert_eq!(transactions[4], [0xC0, 0x00]);
+    }
+}
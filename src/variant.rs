@@ -0,0 +1,69 @@
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::MAX7456;
+
+/// Chips compatible with MAX7456, set by application as there is no documented
+/// way to tell them apart. Only reset and NVM write timing is adjusted, register
+/// behaviour is the same and font memory is 256 characters on all of them
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum ChipVariant {
+    #[default]
+    MAX7456,
+    /// Slower reset and NVM write
+    AT7456E,
+}
+
+// MAX7456 figures follow its datasheet reset and NVM write times, AT7456E
+// ones are not from a datasheet but margins over them for slower clones
+impl ChipVariant {
+    /// Wait after reset before polling for completion
+    pub fn reset_delay_ms(&self) -> u8 {
+        match self {
+            Self::MAX7456 => 50,
+            Self::AT7456E => 100,
+        }
+    }
+
+    /// Wait after NVM write before polling for completion
+    pub fn store_char_delay_ms(&self) -> u8 {
+        match self {
+            Self::MAX7456 => 12,
+            Self::AT7456E => 20,
+        }
+    }
+}
+
+impl<E, PE, SPI, CS, RST> MAX7456<SPI, CS, RST>
+where
+    SPI: Write<u8, Error = E> + Transfer<u8, Error = E>,
+    CS: OutputPin<Error = PE>,
+{
+    pub fn variant(&self) -> ChipVariant {
+        self.variant
+    }
+
+    pub fn set_variant(&mut self, variant: ChipVariant) {
+        self.variant = variant;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ChipVariant;
+    use crate::testing::{MockDelay, Recorder};
+    use crate::MAX7456;
+
+    #[test]
+    fn test_delays() {
+        let recorder = Recorder::new();
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        max7456.set_variant(ChipVariant::AT7456E);
+        let mut delay = MockDelay::default();
+        max7456.reset(&mut delay).unwrap();
+        assert_eq!(delay.elapsed_us, 100_000);
+        let mut delay = MockDelay::default();
+        max7456.store_char(0, &[0u8; 64], &mut delay).unwrap();
+        assert_eq!(delay.elapsed_us, 20_000);
+    }
+}
@@ -0,0 +1,201 @@
+//! Read back register and display memory writes, retrying on mismatch,
+//! for noisy SPI buses like long ribbon cables
+
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+use peripheral_register::Register;
+
+use crate::disassembler::Operation;
+use crate::registers::{
+    field_value, DisplayMemoryMode, OperationMode, Registers, VideoMode0, DMAH, DMAL, DMDI, DMDO,
+    DMM,
+};
+use crate::{Attributes, Display, MAX7456};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct VerifyCounters {
+    /// Writes read back as written, including after retries
+    pub verified: u32,
+    pub retried: u32,
+    /// Writes still mismatching after all retries
+    pub failed: u32,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct Verify {
+    retries: Option<u8>,
+    counters: VerifyCounters,
+}
+
+// registers reading back what was written besides row brightness ones
+const VERIFIABLE: [Registers; 5] = [
+    Registers::VideoMode1,
+    Registers::HorizentalOffset,
+    Registers::VerticalOffset,
+    Registers::OSDInsertionMux,
+    Registers::OSDBlackLevel,
+];
+
+// self clearing software reset bit excluded
+fn is_verifiable(address: u8, value: u8) -> bool {
+    if address == Registers::VideoMode0 as u8 {
+        return field_value(value, VideoMode0::SoftwareReset) == 0;
+    }
+    let mut row_brightness = (0..).map_while(Registers::row_brightness);
+    VERIFIABLE.iter().any(|&reg| reg as u8 == address)
+        || row_brightness.any(|reg| reg as u8 == address)
+}
+
+fn mode_8bit() -> u8 {
+    let mode = OperationMode::Mode8Bit as u8;
+    Register::<u8, DisplayMemoryMode>::of(DisplayMemoryMode::OperationMode, mode).value
+}
+
+impl<E, PE, SPI, CS, RST> MAX7456<SPI, CS, RST>
+where
+    SPI: Write<u8, Error = E> + Transfer<u8, Error = E>,
+    CS: OutputPin<Error = PE>,
+{
+    /// Read back every write, rewriting at most `retries` times on mismatch,
+    /// `None` to disable
+    pub fn set_write_verify(&mut self, retries: Option<u8>) {
+        self.verify.retries = retries;
+    }
+
+    pub fn verify_counters(&self) -> VerifyCounters {
+        self.verify.counters
+    }
+
+    pub fn reset_verify_counters(&mut self) {
+        self.verify.counters = VerifyCounters::default();
+    }
+
+    fn retry<C, W>(&mut self, retries: u8, mut check: C, mut rewrite: W) -> Result<(), E>
+    where
+        C: FnMut(&mut Self) -> Result<bool, E>,
+        W: FnMut(&mut Self) -> Result<(), E>,
+    {
+        for attempt in 0..=retries {
+            if check(self)? {
+                self.verify.counters.verified += 1;
+                return Ok(());
+            }
+            if attempt < retries {
+                self.verify.counters.retried += 1;
+                rewrite(self)?;
+            }
+        }
+        self.verify.counters.failed += 1;
+        Ok(())
+    }
+
+    pub(crate) fn verify_register(&mut self, address: u8, value: u8) -> Result<(), E> {
+        let retries = match self.verify.retries {
+            Some(retries) if is_verifiable(address, value) => retries,
+            _ => return Ok(()),
+        };
+        let check = |s: &mut Self| Ok(s.read_register(address)? == value);
+        self.retry(retries, check, |s| s.send(address, value))
+    }
+
+    fn verify_cell(&mut self, retries: u8, address: u16, byte: u8, dmm: u8) -> Result<(), E> {
+        let check = |s: &mut Self| {
+            s.send(DMM, mode_8bit())?;
+            s.send(DMAH, (address >> 8) as u8)?;
+            s.send(DMAL, address as u8)?;
+            Ok(s.read_register(DMDO)? == byte)
+        };
+        let rewrite = |s: &mut Self| {
+            s.send(DMM, dmm)?;
+            s.send(DMAH, (address >> 8) as u8)?;
+            s.send(DMAL, address as u8)?;
+            s.send(DMDI, byte)
+        };
+        self.retry(retries, check, rewrite)
+    }
+
+    /// Chars only, a cell written more than once within `display` may mismatch
+    pub(crate) fn verify_display(&mut self, display: &Display) -> Result<(), E> {
+        let retries = match self.verify.retries {
+            Some(retries) => retries,
+            None => return Ok(()),
+        };
+        let mut dmm: Option<u8> = None;
+        let mut operations = display.operations();
+        loop {
+            let address = operations.address();
+            match operations.next() {
+                Some(Operation::SetAttributes { attributes, mode, .. }) => {
+                    dmm = Some(match mode {
+                        OperationMode::Mode16Bit => attributes.display_memory_mode(false),
+                        OperationMode::Mode8Bit => mode_8bit(),
+                    });
+                }
+                Some(Operation::WriteChar(byte)) => {
+                    let rewrite =
+                        dmm.unwrap_or_else(|| Attributes::default().display_memory_mode(false));
+                    self.verify_cell(retries, address, byte, rewrite)?;
+                }
+                Some(_) => (),
+                None => break,
+            }
+        }
+        match dmm {
+            Some(dmm) => self.send(DMM, dmm),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_verifiable, VerifyCounters};
+    use crate::incremental_writer::IncrementalWriter;
+    use crate::registers::Registers;
+    use crate::simulator::Simulator;
+    use crate::testing::Recorder;
+    use crate::MAX7456;
+
+    #[test]
+    fn test_register() {
+        let recorder = Recorder::with_simulator(Simulator::new());
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        max7456.set_write_verify(Some(2));
+        max7456.write(Registers::VideoMode1, 0x12).unwrap();
+        recorder.respond(&[0x00, 0x00]); // corrupted twice
+        max7456.write(Registers::VideoMode1, 0x34).unwrap();
+        recorder.respond(&[0x00, 0x00, 0x00]);
+        max7456.write(Registers::VideoMode1, 0x56).unwrap();
+        let expected = VerifyCounters { verified: 2, retried: 4, failed: 1 };
+        assert_eq!(max7456.verify_counters(), expected);
+        max7456.write(Registers::DisplayMemoryDataIn, 0x41).unwrap();
+        assert_eq!(max7456.verify_counters(), expected);
+    }
+
+    #[test]
+    fn test_verifiable() {
+        assert!(is_verifiable(Registers::Row15Brightness as u8, 0x01));
+        assert!(is_verifiable(Registers::OSDBlackLevel as u8, 0x10));
+        assert!(!is_verifiable(Registers::VideoMode0 as u8, 0x02));
+        assert!(!is_verifiable(Registers::DisplayMemoryMode as u8, 0x00));
+    }
+
+    #[test]
+    fn test_display() {
+        let recorder = Recorder::with_simulator(Simulator::new());
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        max7456.set_write_verify(Some(1));
+        let mut writer = IncrementalWriter::new(b"ab", 1, 0, Default::default());
+        let mut buffer = [0u8; 32];
+        let display = writer.write(&mut buffer).unwrap();
+        recorder.respond(b"x"); // first cell corrupted once
+        max7456.write_display(&display).unwrap();
+        let expected = VerifyCounters { verified: 2, retried: 1, failed: 0 };
+        assert_eq!(max7456.verify_counters(), expected);
+        let transactions = recorder.take_transactions();
+        let rewrite: &[&[u8]] = &[&[0x04, 0x00], &[0x05, 0x00], &[0x06, 0x1E], &[0x07, 0x61]];
+        assert_eq!(transactions[5..9], *rewrite);
+        assert_eq!(transactions.last().unwrap(), &[0x04, 0x00]);
+        recorder.simulator(|s| assert_eq!(s.line(1)[..2], *b"ab"));
+    }
+}
@@ -0,0 +1,157 @@
+use core::cmp::min;
+
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::incremental_writer::IncrementalWriter;
+use crate::registers::SyncMode;
+use crate::{Attributes, COLUMN, MAX7456, NTSC_ROW};
+
+const BUFFER_SIZE: usize = 6 + COLUMN * 2 + 2;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Event {
+    /// Switched to internal sync, message shown
+    Lost,
+    /// Switched back to configured sync mode, message erased,
+    /// cells under message should be redrawn
+    Recovered,
+}
+
+/// Keeps OSD rendering on internally generated background when
+/// video input is lost, showing a message until sync returns
+pub struct VideoLossSupervisor<'a> {
+    sync_mode: SyncMode,
+    message: &'a [u8],
+    row: u8,
+    blank: u8,
+    lost: bool,
+}
+
+impl<'a> VideoLossSupervisor<'a> {
+    /// `sync_mode` is restored once sync returns
+    pub fn new(sync_mode: SyncMode) -> Self {
+        let row = (NTSC_ROW / 2) as u8;
+        Self { sync_mode, message: b"VIDEO LOST", row, blank: 0, lost: false }
+    }
+
+    /// Message is centered in `row` and truncated to a row
+    pub fn message(mut self, message: &'a [u8], row: u8) -> Self {
+        self.message = &message[..min(message.len(), COLUMN)];
+        self.row = row;
+        self
+    }
+
+    /// Char erasing message once sync returns
+    pub fn blank(mut self, blank: u8) -> Self {
+        self.blank = blank;
+        self
+    }
+
+    pub fn is_lost(&self) -> bool {
+        self.lost
+    }
+
+    fn write_message<E, PE, SPI, CS, RST>(
+        &self,
+        max7456: &mut MAX7456<SPI, CS, RST>,
+        bytes: &[u8],
+    ) -> Result<(), E>
+    where
+        SPI: Write<u8, Error = E> + Transfer<u8, Error = E>,
+        CS: OutputPin<Error = PE>,
+    {
+        let column = ((COLUMN - bytes.len()) / 2) as u8;
+        let mut writer = IncrementalWriter::new(bytes, self.row, column, Attributes::default());
+        let mut buffer = [0u8; BUFFER_SIZE];
+        while let Some(display) = writer.write(&mut buffer) {
+            max7456.write_display(&display)?;
+        }
+        Ok(())
+    }
+
+    /// Feed loss of sync state, e.g. from LOS pin
+    pub fn update<E, PE, SPI, CS, RST>(
+        &mut self,
+        max7456: &mut MAX7456<SPI, CS, RST>,
+        lost: bool,
+    ) -> Result<Option<Event>, E>
+    where
+        SPI: Write<u8, Error = E> + Transfer<u8, Error = E>,
+        CS: OutputPin<Error = PE>,
+    {
+        if lost == self.lost {
+            return Ok(None);
+        }
+        self.lost = lost;
+        if lost {
+            max7456.set_sync_mode(SyncMode::Internal)?;
+            self.write_message(max7456, self.message)?;
+            return Ok(Some(Event::Lost));
+        }
+        let blank = [self.blank; COLUMN];
+        self.write_message(max7456, &blank[..self.message.len()])?;
+        max7456.set_sync_mode(self.sync_mode)?;
+        Ok(Some(Event::Recovered))
+    }
+
+    /// Read loss of sync from status register
+    pub fn poll<E, PE, SPI, CS, RST>(
+        &mut self,
+        max7456: &mut MAX7456<SPI, CS, RST>,
+    ) -> Result<Option<Event>, E>
+    where
+        SPI: Write<u8, Error = E> + Transfer<u8, Error = E>,
+        CS: OutputPin<Error = PE>,
+    {
+        let lost = max7456.is_sync_lost()?;
+        self.update(max7456, lost)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Event, VideoLossSupervisor};
+    use crate::registers::{Registers, SyncMode};
+    use crate::simulator::Simulator;
+    use crate::testing::Recorder;
+    use crate::MAX7456;
+
+    #[test]
+    fn test_loss_and_recover() {
+        let recorder = Recorder::with_simulator(Simulator::new());
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        let mut supervisor = VideoLossSupervisor::new(SyncMode::External);
+        assert_eq!(supervisor.poll(&mut max7456).unwrap(), None);
+
+        recorder.simulator(|s| s.set_status(0x04));
+        assert_eq!(supervisor.poll(&mut max7456).unwrap(), Some(Event::Lost));
+        assert!(supervisor.is_lost());
+        let vm0 = recorder.simulator(|s| s.register(Registers::VideoMode0)).unwrap();
+        assert_eq!(vm0 & 0x30, 0x30);
+        let line = recorder.simulator(|s| s.line(6).to_vec()).unwrap();
+        assert_eq!(line[10..20], *b"VIDEO LOST");
+        assert_eq!(supervisor.poll(&mut max7456).unwrap(), None);
+
+        recorder.simulator(|s| s.set_status(0x01));
+        assert_eq!(supervisor.poll(&mut max7456).unwrap(), Some(Event::Recovered));
+        let vm0 = recorder.simulator(|s| s.register(Registers::VideoMode0)).unwrap();
+        assert_eq!(vm0 & 0x30, 0x20);
+        let line = recorder.simulator(|s| s.line(6).to_vec()).unwrap();
+        assert_eq!(line, [0u8; 30]);
+    }
+
+    #[test]
+    fn test_message() {
+        let recorder = Recorder::with_simulator(Simulator::new());
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        let mut supervisor =
+            VideoLossSupervisor::new(SyncMode::AutoSyncDetect).message(b"NO CAMERA", 0).blank(b' ');
+        supervisor.update(&mut max7456, true).unwrap();
+        let line = recorder.simulator(|s| s.line(0).to_vec()).unwrap();
+        assert_eq!(line[10..19], *b"NO CAMERA");
+        supervisor.update(&mut max7456, false).unwrap();
+        let line = recorder.simulator(|s| s.line(0).to_vec()).unwrap();
+        assert_eq!(line[10..19], *b"         ");
+    }
+}
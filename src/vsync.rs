@@ -0,0 +1,208 @@
+use embedded_hal::blocking::delay::DelayUs;
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+pub use crate::NoPin;
+use crate::{Display, MAX7456};
+
+// 100us each, two PAL frames
+const VSYNC_POLLS: u16 = 400;
+
+/// Queues display updates and flushes them only at start of vertical blanking,
+/// so that writes won't land mid-frame and tear
+pub struct VSyncWriter<'a, P> {
+    pin: Option<P>,
+    queue: &'a mut [u8],
+    length: usize,
+    in_vsync: bool,
+}
+
+impl<'a> VSyncWriter<'a, NoPin> {
+    pub fn new(queue: &'a mut [u8]) -> Self {
+        Self { pin: None, queue, length: 0, in_vsync: false }
+    }
+}
+
+impl<'a, P: InputPin> VSyncWriter<'a, P> {
+    /// VSYNC pin is active low, falls back to status register if pin fails
+    pub fn with_pin(queue: &'a mut [u8], pin: P) -> Self {
+        Self { pin: Some(pin), queue, length: 0, in_vsync: false }
+    }
+
+    pub fn queued(&self) -> usize {
+        self.length
+    }
+
+    /// Returns false and queues nothing if queue can't hold `display`
+    pub fn queue(&mut self, display: &Display) -> bool {
+        let end = self.length + display.0.len();
+        if end > self.queue.len() {
+            return false;
+        }
+        self.queue[self.length..end].copy_from_slice(display.0);
+        self.length = end;
+        true
+    }
+
+    fn is_in_vsync<E, PE, SPI, CS, RST>(
+        &mut self,
+        max7456: &mut MAX7456<SPI, CS, RST>,
+    ) -> Result<bool, E>
+    where
+        SPI: Write<u8, Error = E> + Transfer<u8, Error = E>,
+        CS: OutputPin<Error = PE>,
+    {
+        match self.pin.as_ref().and_then(|pin| pin.is_low().ok()) {
+            Some(level) => Ok(level),
+            None => max7456.is_in_vsync(),
+        }
+    }
+
+    // false if `polls` run out before VSYNC reaches `in_vsync`
+    fn poll_until<E, PE, SPI, CS, RST>(
+        &mut self,
+        max7456: &mut MAX7456<SPI, CS, RST>,
+        delay: &mut dyn DelayUs<u8>,
+        in_vsync: bool,
+        polls: &mut u16,
+    ) -> Result<bool, E>
+    where
+        SPI: Write<u8, Error = E> + Transfer<u8, Error = E>,
+        CS: OutputPin<Error = PE>,
+    {
+        while self.is_in_vsync(max7456)? != in_vsync {
+            if *polls == 0 {
+                return Ok(false);
+            }
+            *polls -= 1;
+            delay.delay_us(100);
+        }
+        Ok(true)
+    }
+
+    fn flush<E, PE, SPI, CS, RST>(&mut self, max7456: &mut MAX7456<SPI, CS, RST>) -> Result<(), E>
+    where
+        SPI: Write<u8, Error = E> + Transfer<u8, Error = E>,
+        CS: OutputPin<Error = PE>,
+    {
+        if self.length > 0 {
+            max7456.write_display(&Display(&self.queue[..self.length]))?;
+            self.length = 0;
+        }
+        Ok(())
+    }
+
+    /// Non-blocking, flushes queued updates if vertical blanking
+    /// started since last call, returns whether it did
+    pub fn on_vsync<E, PE, SPI, CS, RST>(
+        &mut self,
+        max7456: &mut MAX7456<SPI, CS, RST>,
+    ) -> Result<bool, E>
+    where
+        SPI: Write<u8, Error = E> + Transfer<u8, Error = E>,
+        CS: OutputPin<Error = PE>,
+    {
+        let in_vsync = self.is_in_vsync(max7456)?;
+        let started = in_vsync && !self.in_vsync;
+        self.in_vsync = in_vsync;
+        if started {
+            self.flush(max7456)?;
+        }
+        Ok(started)
+    }
+
+    /// Blocks until start of next vertical blanking then flushes queued updates,
+    /// returns false keeping them queued if no VSYNC seen within two frames
+    pub fn wait_vsync<E, PE, SPI, CS, RST>(
+        &mut self,
+        max7456: &mut MAX7456<SPI, CS, RST>,
+        delay: &mut dyn DelayUs<u8>,
+    ) -> Result<bool, E>
+    where
+        SPI: Write<u8, Error = E> + Transfer<u8, Error = E>,
+        CS: OutputPin<Error = PE>,
+    {
+        let mut polls = VSYNC_POLLS;
+        if !self.poll_until(max7456, delay, false, &mut polls)?
+            || !self.poll_until(max7456, delay, true, &mut polls)?
+        {
+            return Ok(false);
+        }
+        self.in_vsync = true;
+        self.flush(max7456)?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::cell::Cell;
+    use core::convert::Infallible;
+
+    use embedded_hal::digital::v2::InputPin;
+
+    use super::VSyncWriter;
+    use crate::testing::{MockDelay, Recorder};
+    use crate::{Display, MAX7456};
+
+    struct Pin<'a>(&'a Cell<bool>);
+
+    impl<'a> InputPin for Pin<'a> {
+        type Error = Infallible;
+
+        fn is_high(&self) -> Result<bool, Infallible> {
+            Ok(self.0.get())
+        }
+
+        fn is_low(&self) -> Result<bool, Infallible> {
+            Ok(!self.0.get())
+        }
+    }
+
+    #[test]
+    fn test_wait_vsync_by_status() {
+        let recorder = Recorder::new();
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        let mut queue = [0u8; 8];
+        let mut writer = VSyncWriter::new(&mut queue);
+        assert!(writer.queue(&Display(&[0x06, 0x01, 0x07, 0x41])));
+        assert!(!writer.queue(&Display(&[0x06, 0x02, 0x07, 0x42, 0x07, 0xFF])));
+        // in vsync, out of vsync, in vsync
+        recorder.respond(&[0x00, 0x10, 0x00]);
+        let mut delay = MockDelay::default();
+        assert!(writer.wait_vsync(&mut max7456, &mut delay).unwrap());
+        let status: &[u8] = &[0xA0, 0x00];
+        recorder.expect(&[status, status, status, &[0x06, 0x01, 0x07, 0x41]]);
+        assert_eq!(writer.queued(), 0);
+    }
+
+    #[test]
+    fn test_wait_vsync_timeout() {
+        let recorder = Recorder::new(); // absent chip reads as always in vsync
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        let mut queue = [0u8; 8];
+        let mut writer = VSyncWriter::new(&mut queue);
+        writer.queue(&Display(&[0x06, 0x01, 0x07, 0x41]));
+        let mut delay = MockDelay::default();
+        assert!(!writer.wait_vsync(&mut max7456, &mut delay).unwrap());
+        assert_eq!(delay.elapsed_us, 40_000);
+        assert_eq!(writer.queued(), 4);
+    }
+
+    #[test]
+    fn test_on_vsync_by_pin() {
+        let recorder = Recorder::new();
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        let level = Cell::new(true);
+        let mut queue = [0u8; 8];
+        let mut writer = VSyncWriter::with_pin(&mut queue, Pin(&level));
+        writer.queue(&Display(&[0x06, 0x01, 0x07, 0x41]));
+        assert!(!writer.on_vsync(&mut max7456).unwrap());
+        level.set(false);
+        assert!(writer.on_vsync(&mut max7456).unwrap());
+        writer.queue(&Display(&[0x07, 0x42]));
+        assert!(!writer.on_vsync(&mut max7456).unwrap());
+        recorder.expect(&[&[0x06, 0x01, 0x07, 0x41]]);
+        assert_eq!(writer.queued(), 2);
+    }
+}
@@ -0,0 +1,143 @@
+//! Last known configuration, for restoring after chip reset or brown-out
+
+use crate::registers::{field_value, Registers, VideoMode0};
+
+// configuration registers with power-on default, in restore order,
+// video mode 0 last since it enables display. OSD black level default
+// bits are factory preset thus vary, taken as 0
+const CONFIG: [(u8, u8); 22] = [
+    (Registers::VideoMode1 as u8, 0x47),
+    (Registers::HorizentalOffset as u8, 0x20),
+    (Registers::VerticalOffset as u8, 0x10),
+    (Registers::OSDInsertionMux as u8, 0x1B),
+    (Registers::Row0Brightness as u8, 0x01),
+    (Registers::Row1Brightness as u8, 0x01),
+    (Registers::Row2Brightness as u8, 0x01),
+    (Registers::Row3Brightness as u8, 0x01),
+    (Registers::Row4Brightness as u8, 0x01),
+    (Registers::Row5Brightness as u8, 0x01),
+    (Registers::Row6Brightness as u8, 0x01),
+    (Registers::Row7Brightness as u8, 0x01),
+    (Registers::Row8Brightness as u8, 0x01),
+    (Registers::Row9Brightness as u8, 0x01),
+    (Registers::Row10Brightness as u8, 0x01),
+    (Registers::Row11Brightness as u8, 0x01),
+    (Registers::Row12Brightness as u8, 0x01),
+    (Registers::Row13Brightness as u8, 0x01),
+    (Registers::Row14Brightness as u8, 0x01),
+    (Registers::Row15Brightness as u8, 0x01),
+    (Registers::OSDBlackLevel as u8, 0x00),
+    (Registers::VideoMode0 as u8, 0x00),
+];
+
+/// Values written to configuration registers
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub(crate) struct Shadow {
+    values: [Option<u8>; CONFIG.len()],
+}
+
+impl Shadow {
+    pub(crate) fn record(&mut self, address: u8, value: u8) {
+        if address == Registers::VideoMode0 as u8
+            && field_value(value, VideoMode0::SoftwareReset) > 0
+        {
+            *self = Self::default();
+            return;
+        }
+        if let Some(index) = CONFIG.iter().position(|&(a, _)| a == address) {
+            self.values[index] = Some(value);
+        }
+    }
+
+    /// Register/value pairs to be written, in restore order
+    pub(crate) fn restore_sequence(&self) -> impl Iterator<Item = (u8, u8)> + '_ {
+        let values = CONFIG.iter().zip(self.values.iter());
+        values.filter_map(|(&(address, _), value)| value.map(|v| (address, v)))
+    }
+
+    /// A register written with value other than power-on default,
+    /// reading back other value implies chip reset
+    pub(crate) fn probe(&self) -> Option<(u8, u8)> {
+        let mut values = CONFIG.iter().zip(self.values.iter());
+        values.find_map(|(&(address, default), value)| match value {
+            Some(value) if *value != default => Some((address, *value)),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::offset::{HorizontalOffset, VerticalOffset};
+    use crate::registers::{BlackLevel, Registers, Standard};
+    use crate::simulator::Simulator;
+    use crate::testing::{MockDelay, Recorder};
+    use crate::{ResetCheck, MAX7456};
+
+    #[test]
+    fn test_restore() {
+        let recorder = Recorder::with_simulator(Simulator::new());
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        max7456.set_standard(Standard::PAL).unwrap();
+        max7456.set_horizontal_offset_to(HorizontalOffset::new(5).unwrap()).unwrap();
+        max7456.enable_display(true).unwrap();
+        let mut delay = MockDelay::default();
+        assert_eq!(max7456.check_reset(&mut delay).unwrap(), ResetCheck::Running);
+
+        recorder.simulator(|s| s.reset());
+        recorder.take_transactions();
+        assert_eq!(max7456.check_reset(&mut delay).unwrap(), ResetCheck::Restored);
+        let expected: &[&[u8]] =
+            &[&[0xA0, 0x00], &[0x82, 0x00], &[0xA0, 0x00], &[0x02, 0x25], &[0x00, 0x48]];
+        recorder.expect(expected);
+        assert_eq!(max7456.check_reset(&mut delay).unwrap(), ResetCheck::Running);
+    }
+
+    #[test]
+    fn test_restore_black_level() {
+        let recorder = Recorder::with_simulator(Simulator::new());
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        max7456.set_black_level(BlackLevel::Manual(5)).unwrap();
+        recorder.simulator(|s| s.reset());
+        let mut delay = MockDelay::default();
+        assert_eq!(max7456.check_reset(&mut delay).unwrap(), ResetCheck::Restored);
+        assert_eq!(max7456.black_level().unwrap(), BlackLevel::Manual(5));
+    }
+
+    #[test]
+    fn test_software_reset() {
+        let recorder = Recorder::with_simulator(Simulator::new());
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        max7456.set_vertical_offset_to(VerticalOffset::new(-3).unwrap()).unwrap();
+        let mut delay = MockDelay::default();
+        max7456.reset(&mut delay).unwrap();
+        assert_eq!(max7456.check_reset(&mut delay).unwrap(), ResetCheck::Running);
+        let vos = recorder.simulator(|s| s.register(Registers::VerticalOffset)).unwrap();
+        assert_eq!(vos, 0x10);
+    }
+
+    #[test]
+    fn test_reset_mode() {
+        let recorder = Recorder::new();
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        max7456.write(Registers::VideoMode1, 0x47).unwrap();
+        recorder.take_transactions();
+        recorder.respond(&[0x40, 0x40, 0x00]); // in reset, in reset, reset done
+        let mut delay = MockDelay::default();
+        assert_eq!(max7456.check_reset(&mut delay).unwrap(), ResetCheck::Restored);
+        let status: &[u8] = &[0xA0, 0x00];
+        recorder.expect(&[status, status, status, &[0x01, 0x47]]);
+        assert_eq!(delay.elapsed_us, 1000);
+    }
+
+    #[test]
+    fn test_not_responding() {
+        let recorder = Recorder::new();
+        let mut max7456 = MAX7456::new(recorder.spi(), recorder.cs());
+        recorder.respond(&[0xFF; 51]); // MISO floating high
+        let mut delay = MockDelay::default();
+        assert_eq!(max7456.check_reset(&mut delay).unwrap(), ResetCheck::InReset);
+        assert_eq!(delay.elapsed_us, 50_000);
+        assert!(recorder.transactions().iter().all(|t| t[0] == 0xA0));
+    }
+}